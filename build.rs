@@ -0,0 +1,57 @@
+use std::path::Path;
+use std::process::Command;
+
+/// FNV-1a, which must match `strength::hash`
+fn hash(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+/// Words as `strength::corpus_words` finds them
+fn words(line: &str) -> Vec<String> {
+    line.replace('\'', "")
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Hashes every whole entry of quotes.txt and every run of three words in
+/// one, sorted for binary search
+fn quote_ngrams() {
+    let text = std::fs::read_to_string("quotes.txt").unwrap();
+    let mut hashes: Vec<u32> = Vec::new();
+    for line in text.lines().filter(|line| !line.starts_with('#')) {
+        let words = words(line);
+        if words.is_empty() {
+            continue;
+        }
+        hashes.push(hash(&words.join(" ")));
+        hashes.extend(words.windows(3).map(|trigram| hash(&trigram.join(" "))));
+    }
+    hashes.sort_unstable();
+    hashes.dedup();
+
+    let bytes: Vec<u8> = hashes.iter().flat_map(|hash| hash.to_le_bytes()).collect();
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("quote_ngrams.bin");
+    std::fs::write(out, bytes).unwrap();
+}
+
+fn main() {
+    let commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|out| out.status.success())
+        .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    println!("cargo:rustc-env=PASSPHRS_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=PASSPHRS_TARGET={}", std::env::var("TARGET").unwrap());
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    if std::env::var_os("CARGO_FEATURE_QUOTES").is_some() {
+        quote_ngrams();
+        println!("cargo:rerun-if-changed=quotes.txt");
+    }
+}
@@ -0,0 +1,100 @@
+//! `passphrs wordlist audit`: how much strength word lists give for the
+//! typing they take, so that one can be chosen for its effort as well as its
+//! size.
+
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+
+/// A word list's figures, for a passphrase of many words
+struct Efficiency {
+    name: String,
+    words: usize,
+    bits_per_word: f64,
+    average_length: f64,
+    /// Bits per character typed, separator included
+    bits_per_char: f64,
+}
+
+fn measure(name: &str, list: &[String], separator_len: usize) -> Efficiency {
+    let bits_per_word = (list.len() as f64).log2();
+    let average_length = list.iter().map(|w| w.chars().count()).sum::<usize>() as f64 / list.len() as f64;
+    Efficiency {
+        name: name.to_string(),
+        words: list.len(),
+        bits_per_word,
+        average_length,
+        // Each word past the first brings a separator, so over a long
+        // passphrase every word costs one
+        bits_per_char: bits_per_word / (average_length + separator_len as f64),
+    }
+}
+
+/// How much of `list` the `public` words cover, and what its
+/// `length`-word passphrases made only of public words are worth against an
+/// attacker who tries every public word: at most the bits of the public
+/// words however secret the list is kept, and its own bits once it is known
+fn print_overlap(name: &str, list: &[String], public: &HashSet<&str>, length: usize) {
+    let words: HashSet<&str> = list.iter().map(String::as_str).collect();
+    let shared = words.iter().filter(|word| public.contains(*word)).count();
+    let share = (shared as f64 / words.len() as f64).powi(length as i32);
+    let bits = |n: usize| length as f64 * (n as f64).log2();
+    println!("{}: {} of {} words are public, so {:.1}% of {}-word passphrases use only public words. \
+        Those have at most {:.2} bits even while the list is secret, and {:.2} once it is known.",
+        name, shared, words.len(), 100.0 * share, length, bits(public.len()), bits(words.len()));
+}
+
+/// Prints each of `lists` by name with its bits per word and per typed
+/// character, most efficient first. Then, for each of `custom`, how much of
+/// it the `public` lists cover, for passphrases of `length` words.
+pub fn audit_command(lists: &[(String, Vec<String>)], custom: &[(String, Vec<String>)],
+    public: &[(String, Vec<String>)], separator: &str, length: usize) -> Result<()> {
+    let separator_len = separator.chars().count();
+    let mut measured: Vec<Efficiency> = Vec::new();
+    for (name, list) in lists {
+        if list.is_empty() {
+            eprintln!("Skipping {}, which has no words", name);
+            continue;
+        }
+        measured.push(measure(name, list, separator_len));
+    }
+    let custom: Vec<&(String, Vec<String>)> = custom.iter().filter(|(_, list)| !list.is_empty()).collect();
+    if measured.is_empty() {
+        bail!("No word list to audit");
+    }
+    measured.sort_by(|a, b| b.bits_per_char.total_cmp(&a.bits_per_char));
+
+    let width = measured.iter().map(|e| e.name.chars().count()).max().unwrap_or(0).max("List".len());
+    println!("{:<width$}  {:>6}  {:>9}  {:>10}  {:>15}", "List", "Words", "Bits/word", "Avg length", "Bits/typed char",
+        width = width);
+    for e in &measured {
+        println!("{:<width$}  {:>6}  {:>9.2}  {:>10.2}  {:>15.2}",
+            e.name, e.words, e.bits_per_word, e.average_length, e.bits_per_char, width = width);
+    }
+    println!();
+    match separator_len {
+        0 => println!("Words are typed without a separator."),
+        n => println!("Typing counts one {}-character separator per word.", n),
+    }
+    if let [best, .., worst] = &measured[..] {
+        println!("{} needs {:.0}% of the typing {} does for the same strength.",
+            best.name, 100.0 * worst.bits_per_char / best.bits_per_char, worst.name);
+    }
+
+    if custom.is_empty() {
+        return Ok(());
+    }
+    let union: HashSet<&str> = public.iter().flat_map(|(_, list)| list.iter().map(String::as_str)).collect();
+    let names: Vec<&str> = public.iter().map(|(name, _)| name.as_str()).collect();
+    println!();
+    println!("Against an attacker who tries every word of the public lists ({}; {} words together):",
+        names.join(", "), union.len());
+    for (name, list) in custom {
+        print_overlap(name, list, &union, length);
+    }
+    if let Some((name, list)) = public.iter().max_by_key(|(_, list)| list.len()) {
+        println!("For comparison, {} words of {} give {:.2} bits with the list known.",
+            length, name, length as f64 * (list.len() as f64).log2());
+    }
+    Ok(())
+}
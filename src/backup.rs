@@ -0,0 +1,56 @@
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// Backup tools whose repositories can be initialized with a new passphrase
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tool {
+    Restic,
+    Borg,
+}
+
+/// Checks that `tool` runs and that a local `repo` does not exist yet,
+/// without initializing anything
+pub fn check(tool: Tool, repo: &str) -> Result<()> {
+    let program = match tool {
+        Tool::Restic => "restic",
+        Tool::Borg => "borg",
+    };
+    let status = Command::new(program).arg("--version").output()
+        .with_context(|| format!("Could not run {:?}", tool))?.status;
+    if !status.success() {
+        bail!("{} --version failed ({})", program, status);
+    }
+
+    // Remote repositories such as sftp:host:/path can only be checked by
+    // initializing them
+    let local = !repo.contains(':') || std::path::Path::new(repo).is_absolute();
+    if local && std::path::Path::new(repo).exists() {
+        bail!("{} already exists", repo);
+    }
+    Ok(())
+}
+
+/// Initializes `repo` encrypted with `phrase`. The passphrase is handed over
+/// through the tool's documented environment variable, never on argv.
+pub fn init(tool: Tool, repo: &str, phrase: &str) -> Result<()> {
+    let mut command = match tool {
+        Tool::Restic => {
+            let mut command = Command::new("restic");
+            command.args(["init", "--repo", repo]).env("RESTIC_PASSWORD", phrase);
+            command
+        }
+        Tool::Borg => {
+            let mut command = Command::new("borg");
+            command.args(["init", "--encryption=repokey", repo]).env("BORG_PASSPHRASE", phrase);
+            command
+        }
+    };
+
+    let status = command.status().with_context(|| format!("Could not run {:?}", tool))?;
+    if !status.success() {
+        bail!("{:?} failed to initialize {} ({})", tool, repo, status);
+    }
+    eprintln!("Initialized {:?} repository {}. Store the passphrase now: without it the repository cannot be read.", tool, repo);
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// The BIP39 English word list, whose order the checksum depends on
+const WORDS: &str = include_str!("../bip39_english.txt");
+/// Bits each word encodes
+const BITS_PER_WORD: usize = 11;
+
+/// Name of the BIP39 list, as `wordlist audit` reports it
+pub const LIST_NAME: &str = "bip39_english.txt";
+
+/// Word counts BIP39 defines, for 128 to 256 bits of entropy
+pub const LENGTHS: [&str; 5] = ["12", "15", "18", "21", "24"];
+
+/// The BIP39 English words, in order
+pub fn words() -> Vec<String> {
+    WORDS.lines().map(str::to_string).collect()
+}
+
+/// A fresh mnemonic of `words` words, from entropy drawn from the OS
+pub fn mnemonic(words: usize) -> String {
+    // Of every 33 bits, 32 are entropy and one is checksum
+    let entropy_bits = words * BITS_PER_WORD * 32 / 33;
+    let mut entropy = vec![0u8; entropy_bits / 8];
+    rand::rngs::OsRng.fill_bytes(&mut entropy);
+    from_entropy(&entropy)
+}
+
+/// The mnemonic of `entropy`: the entropy followed by the first bits of its
+/// SHA-256 as checksum, read off 11 bits per word
+pub fn from_entropy(entropy: &[u8]) -> String {
+    let list: Vec<&str> = WORDS.lines().collect();
+    let words = entropy.len() * 8 * 33 / 32 / BITS_PER_WORD;
+    let mut bits = entropy.to_vec();
+    bits.extend(Sha256::digest(entropy));
+    let bit = |i: usize| (bits[i / 8] >> (7 - i % 8)) & 1;
+    (0..words)
+        .map(|word| {
+            let index = (0..BITS_PER_WORD).fold(0, |index, i| index << 1 | bit(word * BITS_PER_WORD + i) as usize);
+            list[index]
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,815 @@
+//! Passphrases from a set of settings. The command line tool maps its flags
+//! onto a [`PassphraseBuilder`], so a program using the library gets the same
+//! passphrases, checks and entropy for the same settings.
+
+use std::rc::Rc;
+
+use rand::Rng;
+use thiserror::Error;
+
+use crate::case::{Case, Language, Style};
+use crate::constraint::{self, CharClass, Policy};
+use crate::pattern::{Alphabets, Pattern};
+use crate::transform::{RandomSeparators, Substitute, SubstitutionMap, Transform};
+use crate::wordlist::{self, List};
+use crate::{
+    balance_acceptance, join_parts, select_balanced_words, select_slot_words, select_unique_words, select_words,
+    slot_bits, standard_transforms, unique_bits, LetterWindow, Part,
+};
+
+/// Passphrases generated before giving up on the constraints
+pub const MAX_ATTEMPTS: usize = 100_000;
+/// Longest passphrase a target number of bits will choose
+pub const MAX_LENGTH_FOR_BITS: usize = 100;
+
+/// Why a builder's settings cannot make a passphrase
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum BuildError {
+    #[error("The minimum word length {0} is more than the maximum {1}")]
+    WordLengths(usize, usize),
+    #[error("The word list has no words")]
+    NoWords,
+    #[error("The word list has no words {0}")]
+    NoWordsOfLength(String),
+    #[error("Every word in the list contains a forbidden character")]
+    WordsForbidden,
+    #[error("The list for the {0} slot has no usable words")]
+    SlotForbidden(String),
+    #[error("The separator contains a forbidden character")]
+    SeparatorForbidden,
+    #[error("The set of random separators has no characters that are not forbidden")]
+    SeparatorsForbidden,
+    #[error("The prefix contains a forbidden character")]
+    PrefixForbidden,
+    #[error("The salt characters are empty, so there is nothing to draw {0} characters of salt from")]
+    NoSaltChars(usize),
+    #[error("Every salt character is forbidden")]
+    SaltForbidden,
+    #[error("Every substitution has a forbidden character")]
+    SubstitutionsForbidden,
+    #[error("The pattern needs a {0} character, and every one is forbidden")]
+    PatternClass(&'static str),
+    #[error("The prefix starts with '{0}', which the start constraint rules out")]
+    PrefixStart(char),
+    #[error("Never repeating a word needs at least {0} words, and the list has {1}")]
+    TooFewWords(usize, usize),
+    #[error("No {0} words come to {1}±{2} characters with these settings")]
+    NoBalance(usize, usize, usize),
+    #[error("An empty passphrase cannot meet the start/end constraints; add words or salt")]
+    EmptyPhrase,
+    #[error("No passphrase can satisfy the start/end constraints with these settings")]
+    Unsatisfiable,
+    #[error("Too few passphrases meet the required classes and character limits with these settings; try fewer or more words")]
+    PolicyUnsatisfiable,
+    #[error("{0} words are not enough for {1} bits with these settings")]
+    BitsUnreachable(usize, f64),
+    #[error("Could not satisfy the constraints after {0} attempts")]
+    Attempts(usize),
+}
+
+/// One step of a builder's pipeline
+#[derive(Clone, Debug)]
+enum Step {
+    /// `standard_transforms` of the case and salt settings
+    Standard,
+    /// A separator from the set in each gap, less any forbidden
+    Separators(String),
+    /// Look-alike substitutions, less those with a forbidden character
+    Substitute(SubstitutionMap, f64),
+    Custom(Rc<dyn Transform>),
+}
+
+/// How the words of a passphrase are picked
+#[derive(Clone, Debug)]
+enum Selection {
+    Any,
+    Unique,
+    /// Words whose lengths, with separators and salt, come to `.0` ± `.1`
+    /// characters
+    Balanced(usize, usize),
+    /// A word from each named list in turn
+    Slots(Vec<(String, Vec<String>)>),
+    Pattern(Pattern),
+}
+
+/// Where the bits of entropy of a scheme come from
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bits {
+    /// The words, less what truncating them, never repeating them or keeping
+    /// their lengths in a window costs. Steps added with `transform` count
+    /// here.
+    pub words: f64,
+    pub separators: f64,
+    pub salt_position: f64,
+    /// The salt, or the random characters of a pattern
+    pub salt_characters: f64,
+    pub case: f64,
+    pub substitutions: f64,
+    /// Negative: what regenerating passphrases that break a constraint or the
+    /// policy costs
+    pub constraints: f64,
+}
+
+impl Bits {
+    pub fn total(&self) -> f64 {
+        self.words + self.separators + self.salt_position + self.salt_characters + self.case + self.substitutions
+            + self.constraints
+    }
+
+    /// The length of a random ASCII password that would be as strong
+    pub fn equivalent_ascii_characters(&self) -> f64 {
+        self.total() / 7.0
+    }
+}
+
+/// The settings a passphrase was generated with
+#[derive(Clone, Debug)]
+pub struct Params {
+    pub length: usize,
+    pub separator: String,
+    pub salt_length: usize,
+    pub salt_chars: String,
+    pub case: Case,
+    pub unique: bool,
+}
+
+/// A generated passphrase with what is known about how it was made, so
+/// callers need not recompute it
+#[derive(Clone, Debug)]
+pub struct GeneratedPassphrase {
+    pub secret: String,
+    /// Bits of entropy against an attacker who knows `params`
+    pub entropy_bits: f64,
+    /// The words, separators and salt that make up `secret`
+    pub components: Vec<Part>,
+    /// Name of the word list the words came from
+    pub wordlist_id: String,
+    pub params: Params,
+}
+
+/// Settings for generating passphrases, starting from the same defaults as
+/// the command line: seven capitalized words from the EFF large list,
+/// separated by spaces, with one digit of salt
+///
+/// The chosen words go through a pipeline of [`Transform`]s in order, which
+/// starts as only the standard casing and salt:
+///
+/// ```
+/// use passphrs::{Part, PassphraseBuilder};
+/// use passphrs::transform::Truncate;
+///
+/// // Truncated before casing, with a random separator in each gap
+/// let generated = PassphraseBuilder::default()
+///     .transforms(Vec::new())
+///     .transform(Truncate(4))
+///     .random_separators("-.")
+///     .standard()
+///     .build()
+///     .unwrap();
+/// for part in &generated.components {
+///     match part {
+///         Part::Word(word, _) => assert!(word.chars().count() <= 4),
+///         Part::Separator(separator) => assert!(separator == "-" || separator == "."),
+///         _ => {}
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct PassphraseBuilder {
+    wordlist_id: String,
+    words: Vec<String>,
+    length: usize,
+    bits: Option<f64>,
+    separator: String,
+    salt_length: usize,
+    salt_chars: String,
+    case: Case,
+    selection: Selection,
+    min_word_len: Option<usize>,
+    max_word_len: Option<usize>,
+    forbidden: Vec<char>,
+    prefix: String,
+    start: Option<CharClass>,
+    end: Option<CharClass>,
+    policy: Policy,
+    /// The username a Windows-complex passphrase must not contain, if held
+    /// to those rules
+    windows: Option<Option<String>>,
+    pipeline: Vec<Step>,
+}
+
+impl Default for PassphraseBuilder {
+    fn default() -> Self {
+        PassphraseBuilder::new(List::EffLarge.name(), wordlist::parse(List::EffLarge.text(), false, None, None))
+    }
+}
+
+impl PassphraseBuilder {
+    /// Starts from the defaults with a custom word list, identified in
+    /// results as `id`
+    pub fn new(id: &str, words: Vec<String>) -> Self {
+        PassphraseBuilder {
+            wordlist_id: id.to_string(),
+            words,
+            length: 7,
+            bits: None,
+            separator: " ".to_string(),
+            salt_length: 1,
+            salt_chars: constraint::DIGITS.to_string(),
+            case: Case::Style(Style::Capitalized, Language::Unicode),
+            selection: Selection::Any,
+            min_word_len: None,
+            max_word_len: None,
+            forbidden: Vec::new(),
+            prefix: String::new(),
+            start: None,
+            end: None,
+            policy: Policy::default(),
+            windows: None,
+            pipeline: vec![Step::Standard],
+        }
+    }
+
+    /// Number of words. 0 generates only salt.
+    pub fn length(mut self, length: usize) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// Uses the fewest words, up to `MAX_LENGTH_FOR_BITS`, that reach this
+    /// many bits, instead of `length`
+    pub fn bits(mut self, bits: Option<f64>) -> Self {
+        self.bits = bits;
+        self
+    }
+
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.to_string();
+        self
+    }
+
+    /// Number of salt characters added after a random word
+    pub fn salt_length(mut self, salt_length: usize) -> Self {
+        self.salt_length = salt_length;
+        self
+    }
+
+    pub fn salt_chars(mut self, salt_chars: &str) -> Self {
+        self.salt_chars = salt_chars.to_string();
+        self
+    }
+
+    pub fn case(mut self, case: Case) -> Self {
+        self.case = case;
+        self
+    }
+
+    /// Never uses a word twice in one passphrase. The list needs at least
+    /// `length` words.
+    pub fn unique(mut self, unique: bool) -> Self {
+        if unique {
+            self.selection = Selection::Unique;
+        } else if let Selection::Unique = self.selection {
+            self.selection = Selection::Any;
+        }
+        self
+    }
+
+    /// Picks words so that words, separators and salt come to `target` ±
+    /// `tolerance` characters, uniformly among the passphrases that do
+    pub fn balance(mut self, target: usize, tolerance: usize) -> Self {
+        self.selection = Selection::Balanced(target, tolerance);
+        self
+    }
+
+    /// Draws each word from its own list in turn, instead of the word list.
+    /// The names are for errors. Sets the length.
+    pub fn slots(mut self, slots: Vec<(String, Vec<String>)>) -> Self {
+        self.length = slots.len();
+        self.selection = Selection::Slots(slots);
+        self
+    }
+
+    /// Spells out the passphrase with `pattern`, which places its own words
+    /// and characters. Sets the length, leaves out the salt and skips the
+    /// standard casing and salt in the pipeline.
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        self.length = pattern.words();
+        self.salt_length = 0;
+        self.selection = Selection::Pattern(pattern);
+        self
+    }
+
+    /// Only uses words with at least `min` and at most `max` characters
+    pub fn word_len(mut self, min: Option<usize>, max: Option<usize>) -> Self {
+        self.min_word_len = min;
+        self.max_word_len = max;
+        self
+    }
+
+    /// Guarantees that none of `chars` appear anywhere in a passphrase.
+    /// Words that could come out with one are dropped, and so are salt
+    /// characters, random separators and substitutions that are one.
+    pub fn forbid(mut self, chars: &str) -> Self {
+        self.forbidden = chars.chars().collect();
+        self
+    }
+
+    /// Starts every passphrase with `prefix`
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Regenerates each passphrase until it starts with `class`
+    pub fn must_start_with(mut self, class: Option<CharClass>) -> Self {
+        self.start = class;
+        self
+    }
+
+    /// Regenerates each passphrase until it ends with `class`
+    pub fn must_end_with(mut self, class: Option<CharClass>) -> Self {
+        self.end = class;
+        self
+    }
+
+    /// Regenerates each passphrase until `policy` allows it
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Regenerates each passphrase until it meets the default Windows
+    /// complexity rules, without containing `username`
+    pub fn windows_complex(mut self, username: Option<&str>) -> Self {
+        self.windows = Some(username.map(str::to_string));
+        self
+    }
+
+    /// Adds `transform` to the end of the pipeline
+    pub fn transform<T: Transform + 'static>(mut self, transform: T) -> Self {
+        self.pipeline.push(Step::Custom(Rc::new(transform)));
+        self
+    }
+
+    /// Replaces the pipeline with `transforms`, run in order. Casing and
+    /// salt are left out unless they are among them or added back with
+    /// `standard`.
+    pub fn transforms(mut self, transforms: Vec<Box<dyn Transform>>) -> Self {
+        self.pipeline = transforms.into_iter().map(|transform| Step::Custom(Rc::from(transform))).collect();
+        self
+    }
+
+    /// Adds the casing and salt of this builder's settings to the end of
+    /// the pipeline, as `standard_transforms`
+    pub fn standard(mut self) -> Self {
+        self.pipeline.push(Step::Standard);
+        self
+    }
+
+    /// Adds a step replacing each separator with one drawn from `chars` to
+    /// the end of the pipeline. The first character that is not forbidden
+    /// stands in for them wherever only the separator's length matters.
+    pub fn random_separators(mut self, chars: &str) -> Self {
+        self.pipeline.push(Step::Separators(chars.to_string()));
+        self
+    }
+
+    /// Adds a step replacing letters with look-alikes from `map`, each with
+    /// probability `prob`, to the end of the pipeline
+    pub fn substitute(mut self, map: SubstitutionMap, prob: f64) -> Self {
+        self.pipeline.push(Step::Substitute(map, prob));
+        self
+    }
+
+    /// Uses a custom word list, identified in results as `id`
+    pub fn wordlist(mut self, id: &str, words: Vec<String>) -> Self {
+        self.wordlist_id = id.to_string();
+        self.words = words;
+        self
+    }
+
+    /// Uses one of the bundled word lists
+    pub fn list(self, list: List) -> Self {
+        self.wordlist(list.name(), wordlist::parse(list.text(), false, None, None))
+    }
+
+
+    /// Checks the settings and filters the words once, for generating many
+    /// passphrases
+    pub fn scheme(&self) -> Result<Scheme, BuildError> {
+        let base = self.filter()?;
+        match self.bits {
+            Some(bits) => (1..=MAX_LENGTH_FOR_BITS)
+                .filter_map(|length| base.measure(length).ok())
+                .find(|scheme| scheme.bits.total() >= bits)
+                .ok_or(BuildError::BitsUnreachable(MAX_LENGTH_FOR_BITS, bits)),
+            None => base.measure(self.length),
+        }
+    }
+
+    /// Generates a passphrase with the thread random number generator
+    pub fn build(&self) -> Result<GeneratedPassphrase, BuildError> {
+        self.build_with(&mut rand::thread_rng())
+    }
+
+    /// Generates a passphrase with the given random number generator
+    pub fn build_with<R: Rng>(&self, rng: &mut R) -> Result<GeneratedPassphrase, BuildError> {
+        let scheme = self.scheme()?;
+        let components = scheme.generate_with(rng)?;
+        Ok(GeneratedPassphrase {
+            secret: join_parts(&components),
+            entropy_bits: scheme.bits.total(),
+            components,
+            wordlist_id: self.wordlist_id.clone(),
+            params: Params {
+                length: scheme.length,
+                separator: scheme.separator.clone(),
+                salt_length: self.salt_length,
+                salt_chars: scheme.salt_chars.clone(),
+                case: self.case.clone(),
+                unique: matches!(self.selection, Selection::Unique),
+            },
+        })
+    }
+
+    /// Bits of entropy against an attacker who knows these settings
+    pub fn entropy(&self) -> Result<f64, BuildError> {
+        Ok(self.scheme()?.bits.total())
+    }
+
+    /// The scheme for everything that does not depend on the number of words
+    fn filter(&self) -> Result<Scheme, BuildError> {
+        if let (Some(min), Some(max)) = (self.min_word_len, self.max_word_len) {
+            if min > max {
+                return Err(BuildError::WordLengths(min, max));
+            }
+        }
+        let forbidden = &self.forbidden;
+        let allowed = |chars: &str| -> String { chars.chars().filter(|c| !forbidden.contains(c)).collect() };
+        if self.separator.chars().any(|c| forbidden.contains(&c)) {
+            return Err(BuildError::SeparatorForbidden);
+        }
+        if self.prefix.chars().any(|c| forbidden.contains(&c)) {
+            return Err(BuildError::PrefixForbidden);
+        }
+        if self.salt_length > 0 && self.salt_chars.is_empty() {
+            return Err(BuildError::NoSaltChars(self.salt_length));
+        }
+        let salt_chars = allowed(&self.salt_chars);
+        if self.salt_length > 0 && salt_chars.is_empty() {
+            return Err(BuildError::SaltForbidden);
+        }
+        let pattern = match &self.selection {
+            Selection::Pattern(pattern) => Some(pattern.clone()),
+            _ => None,
+        };
+        let alphabets = Alphabets {
+            digits: allowed(constraint::DIGITS),
+            symbols: allowed(constraint::SYMBOLS),
+            salt: salt_chars.clone(),
+        };
+        if let Some(class) = pattern.as_ref().and_then(|pattern| pattern.empty_class(&alphabets)) {
+            return Err(BuildError::PatternClass(class));
+        }
+
+        // Each filter that can empty the list says so, rather than leaving
+        // the forbidden characters to take the blame
+        if self.words.is_empty() {
+            return Err(BuildError::NoWords);
+        }
+        let within = |words: &[String]| wordlist::within(words.to_vec(), self.min_word_len, self.max_word_len);
+        let words = within(&self.words);
+        if words.is_empty() {
+            return Err(BuildError::NoWordsOfLength(match (self.min_word_len, self.max_word_len) {
+                (Some(min), Some(max)) => format!("between {} and {} letters", min, max),
+                (Some(min), None) => format!("of {} or more letters", min),
+                (None, Some(max)) => format!("of {} or fewer letters", max),
+                (None, None) => unreachable!("only the length bounds drop words here"),
+            }));
+        }
+        let lower_words = pattern.as_ref().map_or(0, Pattern::lower_words);
+        let lower = Case::Style(Style::Lower, self.case.language());
+        let words: Vec<String> = words.into_iter()
+            .filter(|w| !self.case.may_produce(w, forbidden) && (lower_words == 0 || !lower.may_produce(w, forbidden)))
+            .collect();
+        if words.is_empty() {
+            return Err(BuildError::WordsForbidden);
+        }
+        // Casing and the start/end constraints are measured on the words of
+        // every slot together
+        let mut slots = Vec::new();
+        if let Selection::Slots(named) = &self.selection {
+            for (name, list) in named {
+                let list: Vec<String> = within(list).into_iter().filter(|w| !self.case.may_produce(w, forbidden)).collect();
+                if list.is_empty() {
+                    return Err(BuildError::SlotForbidden(name.clone()));
+                }
+                slots.push(list);
+            }
+        }
+        let words = if let Selection::Slots(_) = self.selection { slots.concat() } else { words };
+
+        let mut separator = self.separator.clone();
+        let mut steps: Vec<(Kind, Rc<dyn Transform>)> = Vec::new();
+        for step in &self.pipeline {
+            match step {
+                // A pattern cases its own words and has no salt
+                Step::Standard if pattern.is_some() => {}
+                Step::Standard => {
+                    let mut standard = standard_transforms(&self.case, self.salt_length, &salt_chars).into_iter();
+                    steps.extend(standard.next().map(|case| (Kind::Case, Rc::from(case))));
+                    steps.extend(standard.next().map(|salt| (Kind::Salt, Rc::from(salt))));
+                }
+                // The first of the set stands in for them wherever only the
+                // separator's length matters
+                Step::Separators(set) => {
+                    let mut chars: Vec<char> = Vec::new();
+                    for c in set.chars().filter(|c| !forbidden.contains(c)) {
+                        if !chars.contains(&c) {
+                            chars.push(c);
+                        }
+                    }
+                    match chars.first() {
+                        Some(first) => separator = first.to_string(),
+                        None => return Err(BuildError::SeparatorsForbidden),
+                    }
+                    steps.push((Kind::Separators, Rc::new(RandomSeparators { chars: chars.into_iter().collect() })));
+                }
+                Step::Substitute(map, prob) => {
+                    let map: Vec<(char, String)> = map.0.iter()
+                        .filter(|(_, to)| !to.chars().any(|c| forbidden.contains(&c)))
+                        .cloned()
+                        .collect();
+                    if map.is_empty() {
+                        return Err(BuildError::SubstitutionsForbidden);
+                    }
+                    steps.push((Kind::Substitutions, Rc::new(Substitute { map: SubstitutionMap(map), prob: *prob })));
+                }
+                Step::Custom(transform) => steps.push((Kind::Other, transform.clone())),
+            }
+        }
+
+        Ok(Scheme {
+            words,
+            slots,
+            length: self.length,
+            separator,
+            salt_length: self.salt_length,
+            salt_chars,
+            case: self.case.clone(),
+            pattern,
+            alphabets,
+            unique: matches!(self.selection, Selection::Unique),
+            balance: match self.selection {
+                Selection::Balanced(target, tolerance) => Some((target, tolerance)),
+                _ => None,
+            },
+            window: None,
+            prefix: self.prefix.clone(),
+            start: self.start,
+            end: self.end,
+            policy: self.policy.clone(),
+            windows: self.windows.clone(),
+            steps,
+            bits: Bits::default(),
+        })
+    }
+}
+
+/// What part of the entropy a step's bits are
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Case,
+    Salt,
+    Separators,
+    Substitutions,
+    Other,
+}
+
+/// A builder's settings, checked and with the words that can be used, ready
+/// to generate passphrases from
+#[derive(Clone, Debug)]
+pub struct Scheme {
+    words: Vec<String>,
+    slots: Vec<Vec<String>>,
+    length: usize,
+    separator: String,
+    salt_length: usize,
+    salt_chars: String,
+    case: Case,
+    pattern: Option<Pattern>,
+    alphabets: Alphabets,
+    unique: bool,
+    balance: Option<(usize, usize)>,
+    window: Option<LetterWindow>,
+    prefix: String,
+    start: Option<CharClass>,
+    end: Option<CharClass>,
+    policy: Policy,
+    windows: Option<Option<String>>,
+    steps: Vec<(Kind, Rc<dyn Transform>)>,
+    bits: Bits,
+}
+
+impl Scheme {
+    /// The words passphrases are drawn from, after filtering. With slots,
+    /// those of every slot.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Number of words, as chosen for a target number of bits if one was set
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// The separator, or the one standing in for random separators
+    pub fn separator(&self) -> &str {
+        &self.separator
+    }
+
+    /// The salt characters that are not forbidden
+    pub fn salt_chars(&self) -> &str {
+        &self.salt_chars
+    }
+
+    /// Bits of entropy against an attacker who knows the settings
+    pub fn bits(&self) -> Bits {
+        self.bits
+    }
+
+    /// Whether a finished passphrase meets every rule it is regenerated for
+    pub fn complies(&self, phrase: &str) -> bool {
+        constraint::satisfied(phrase, self.start, self.end)
+            && self.windows.as_ref().is_none_or(|username| constraint::windows_complex(phrase, username.as_deref()))
+            && self.policy.allows(phrase)
+    }
+
+    /// Generates a passphrase that meets every rule, trying up to
+    /// `MAX_ATTEMPTS` times
+    pub fn generate_with<R: Rng>(&self, rng: &mut R) -> Result<Vec<Part>, BuildError> {
+        for _ in 0..MAX_ATTEMPTS {
+            let parts = self.draw(rng);
+            if self.complies(&join_parts(&parts)) {
+                return Ok(parts);
+            }
+        }
+        Err(BuildError::Attempts(MAX_ATTEMPTS))
+    }
+
+    /// Generates a passphrase, whether or not it meets the rules
+    fn draw<R: Rng>(&self, rng: &mut R) -> Vec<Part> {
+        let mut parts = if let Some(pattern) = &self.pattern {
+            pattern.build(rng, &self.words, &self.case, self.case.language(), &self.alphabets)
+        } else if !self.slots.is_empty() {
+            select_slot_words(rng, &self.slots, &self.separator)
+        } else if let Some(window) = self.window {
+            select_balanced_words(rng, &self.words, self.length, &self.separator, window)
+        } else if self.unique {
+            select_unique_words(rng, &self.words, self.length, &self.separator)
+        } else {
+            select_words(rng, &self.words, self.length, &self.separator)
+        };
+        // No step moves or draws for anything but words and separators, so
+        // the prefix can go first
+        if !self.prefix.is_empty() {
+            parts.insert(0, Part::Literal(self.prefix.clone()));
+        }
+        for (_, step) in &self.steps {
+            step.apply(&mut parts, rng);
+        }
+        parts
+    }
+
+    /// This scheme with `length` words, checked and measured
+    fn measure(&self, length: usize) -> Result<Scheme, BuildError> {
+        let mut scheme = Scheme { length, ..self.clone() };
+        let n = scheme.words.len();
+        if scheme.unique && length > n {
+            return Err(BuildError::TooFewWords(length, n));
+        }
+
+        // Each step is measured on the words as the steps before it leave
+        // them, and the constraints on the words as they will appear
+        let mut bits = Bits { words: length as f64 * (n as f64).log2(), ..Bits::default() };
+        let mut measured = scheme.words.clone();
+        for (kind, step) in &scheme.steps {
+            let step_bits = step.bits(&measured, length);
+            match kind {
+                Kind::Case => bits.case += step_bits,
+                Kind::Salt if scheme.salt_length > 0 => {
+                    bits.salt_position += (length.max(1) as f64).log2();
+                    bits.salt_characters += scheme.salt_length as f64 * (scheme.salt_chars.chars().count() as f64).log2();
+                }
+                Kind::Salt => {}
+                Kind::Separators => bits.separators += step_bits,
+                Kind::Substitutions => bits.substitutions += step_bits,
+                Kind::Other => bits.words += step_bits,
+            }
+            if let Some(words) = step.words(&measured) {
+                measured = words;
+            }
+        }
+        if let Some(pattern) = &scheme.pattern {
+            bits.case += length.saturating_sub(pattern.lower_words()) as f64 * scheme.case.entropy(&measured);
+            bits.salt_characters += pattern.char_bits(&scheme.alphabets);
+        }
+        if !scheme.slots.is_empty() {
+            bits.words += slot_bits(&scheme.slots) - length as f64 * (n as f64).log2();
+        }
+        if scheme.unique {
+            bits.words += unique_bits(n, length);
+        }
+        // The words get what the balance target leaves after separators and
+        // salt
+        if let Some((target, tolerance)) = scheme.balance {
+            let fixed = scheme.separator.chars().count() * length.saturating_sub(1) + scheme.salt_length;
+            let window = LetterWindow {
+                min: target.saturating_sub(tolerance).saturating_sub(fixed),
+                max: (target + tolerance).saturating_sub(fixed),
+            };
+            let acceptance = balance_acceptance(&scheme.words, length, window);
+            if acceptance == 0.0 {
+                return Err(BuildError::NoBalance(length, target, tolerance));
+            }
+            bits.words += acceptance.log2();
+            scheme.window = Some(window);
+        }
+
+        // A prefix decides how the passphrase starts, leaving the words
+        // nothing to meet there
+        let start = match (scheme.start, scheme.prefix.chars().next()) {
+            (Some(class), Some(first)) if !class.matches(first) => return Err(BuildError::PrefixStart(first)),
+            (Some(_), Some(_)) => None,
+            (start, _) => start,
+        };
+        if length == 0 && scheme.salt_length == 0 && scheme.pattern.is_none() && (start.is_some() || scheme.end.is_some()) {
+            return Err(BuildError::EmptyPhrase);
+        }
+        // A pattern rules out the start/end constraints
+        let acceptance = if scheme.pattern.is_some() || (start.is_none() && scheme.end.is_none()) {
+            1.0
+        } else {
+            constraint::acceptance(&measured, length, scheme.salt_length, &scheme.salt_chars, &scheme.case, start, scheme.end)
+        };
+        if acceptance == 0.0 {
+            return Err(BuildError::Unsatisfiable);
+        }
+        // Sampled, taking it to be independent of the start/end constraints
+        let policy_acceptance = scheme.policy.acceptance(|| join_parts(&scheme.draw(&mut rand::thread_rng())));
+        if policy_acceptance == 0.0 {
+            return Err(BuildError::PolicyUnsatisfiable);
+        }
+        bits.constraints = (acceptance * policy_acceptance).log2();
+
+        scheme.bits = bits;
+        Ok(scheme)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use super::{BuildError, PassphraseBuilder};
+    use crate::transform::parse_substitutions;
+
+    #[test]
+    fn errors_name_the_filter() {
+        let builder = PassphraseBuilder::default();
+        assert_eq!(builder.clone().word_len(Some(4), Some(3)).scheme().unwrap_err(), BuildError::WordLengths(4, 3));
+        assert_eq!(builder.clone().word_len(None, Some(0)).scheme().unwrap_err(),
+            BuildError::NoWordsOfLength("of 0 or fewer letters".to_string()));
+        assert_eq!(builder.clone().forbid("abcdefghijklmnopqrstuvwxyz").scheme().unwrap_err(), BuildError::WordsForbidden);
+        assert_eq!(builder.clone().salt_chars("").scheme().unwrap_err(), BuildError::NoSaltChars(1));
+        assert_eq!(builder.clone().salt_chars("7").forbid("7").scheme().unwrap_err(), BuildError::SaltForbidden);
+        assert_eq!(builder.unique(true).wordlist("tiny", vec!["a".to_string()]).scheme().unwrap_err(),
+            BuildError::TooFewWords(7, 1));
+    }
+
+    #[test]
+    fn forbidden_characters_never_appear() {
+        let builder = PassphraseBuilder::default()
+            .forbid("e1-")
+            .random_separators("-.")
+            .substitute(parse_substitutions("a=@,i=1,o=0").unwrap(), 0.5);
+        let scheme = builder.scheme().unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(1);
+        for _ in 0..200 {
+            let phrase = crate::join_parts(&scheme.generate_with(&mut rng).unwrap());
+            assert!(!phrase.contains(|c| "e1-".contains(c)), "{}", phrase);
+        }
+    }
+
+    #[test]
+    fn bits_choose_the_length() {
+        let default = PassphraseBuilder::default();
+        let expected = crate::entropy(7776, 7, 1, 1, crate::constraint::DIGITS, 0.0).0;
+        assert!((default.entropy().unwrap() - expected).abs() < 1e-9);
+        // Four words and a digit come to 57 bits, five to 70
+        assert_eq!(default.clone().bits(Some(60.0)).scheme().unwrap().length(), 5);
+        assert_eq!(default.bits(Some(1e6)).scheme().unwrap_err(), BuildError::BitsUnreachable(100, 1e6));
+    }
+}
@@ -0,0 +1,326 @@
+use clap::ArgEnum;
+use rand::Rng;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// A single position of a case mask
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MaskPos {
+    Upper,
+    Lower,
+    Random,
+    Keep,
+}
+
+/// Per-letter case mask. `U` uppercases, `l` lowercases, `?` picks randomly
+/// and `.` leaves the letter unchanged. The last position repeats for the
+/// rest of the word, so `Ul` capitalizes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CaseMask(Vec<MaskPos>);
+
+impl CaseMask {
+    fn at(&self, i: usize) -> MaskPos {
+        self.0[i.min(self.0.len() - 1)]
+    }
+}
+
+pub fn parse_mask(s: &str) -> Result<CaseMask, String> {
+    let mask = s.chars()
+        .map(|c| match c {
+            'U' => Ok(MaskPos::Upper),
+            'l' => Ok(MaskPos::Lower),
+            '?' => Ok(MaskPos::Random),
+            '.' => Ok(MaskPos::Keep),
+            _ => Err(format!("invalid case mask character '{}', expected one of U l ? .", c)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if mask.is_empty() {
+        return Err("case mask is empty".to_string());
+    }
+    Ok(CaseMask(mask))
+}
+
+pub fn parse_prob(s: &str) -> Result<f64, String> {
+    let p: f64 = s.parse().map_err(|e| format!("{}", e))?;
+    if (0.0..=1.0).contains(&p) {
+        Ok(p)
+    } else {
+        Err("probability must be between 0 and 1".to_string())
+    }
+}
+
+/// Language whose rules decide what a letter's other case is
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    /// Unicode default mappings, e.g. ß uppercases to SS
+    Unicode,
+    /// Turkish and Azerbaijani, where i and ı are distinct letters whose
+    /// capitals are İ and I
+    Turkish,
+    /// Dutch, where a word starting with ij is capitalized as IJ
+    Dutch,
+}
+
+impl Language {
+    /// The uppercase form of `c`, which may be several characters
+    pub fn upper(self, c: char) -> String {
+        match (self, c) {
+            (Language::Turkish, 'i') => "\u{130}".to_string(),
+            _ => c.to_uppercase().collect(),
+        }
+    }
+
+    /// The lowercase form of `c`, which may be several characters
+    pub fn lower(self, c: char) -> String {
+        match (self, c) {
+            (Language::Turkish, 'I') => "\u{131}".to_string(),
+            (Language::Turkish, '\u{130}') => "i".to_string(),
+            _ => c.to_lowercase().collect(),
+        }
+    }
+
+    /// Number of leading characters of `word` that capitalizing uppercases:
+    /// its first grapheme cluster, so that combining marks stay with their
+    /// letter, or the ij digraph in Dutch
+    fn capital_len(self, word: &str) -> usize {
+        let mut clusters = word.graphemes(true);
+        let first = clusters.next().unwrap_or("");
+        let digraph = self == Language::Dutch
+            && first.eq_ignore_ascii_case("i")
+            && clusters.next().is_some_and(|second| second.eq_ignore_ascii_case("j"));
+        if digraph { 2 } else { first.chars().count() }
+    }
+
+    /// Uppercases the first grapheme cluster of `word`. Clusters never split,
+    /// so words starting with multi-byte or decomposed letters are safe.
+    pub fn capitalize(self, word: &str) -> String {
+        let capital = self.capital_len(word);
+        word.chars()
+            .enumerate()
+            .map(|(i, c)| if i < capital { self.upper(c) } else { c.to_string() })
+            .collect()
+    }
+
+    fn cased(self, c: char, upper: bool) -> String {
+        if upper { self.upper(c) } else { self.lower(c) }
+    }
+}
+
+/// A fixed or random way of casing each word. The numbers the styles once
+/// were are still accepted.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Style {
+    /// Leave words as the list has them
+    #[clap(alias = "0")]
+    None,
+    #[clap(alias = "1")]
+    Lower,
+    #[clap(alias = "2")]
+    Capitalized,
+    #[clap(alias = "3")]
+    Upper,
+    /// Lowercase first word, the rest capitalized, unseparated by default
+    Camel,
+    /// Every word capitalized, unseparated by default
+    Pascal,
+    /// Each word lowercase, capitalized or uppercase at random
+    Random,
+    /// Letters alternately lower and uppercase, as in aLtErNaTiNg
+    Alternating,
+}
+
+impl Style {
+    /// Whether words run together unless a separator is set
+    pub fn joins_words(self) -> bool {
+        matches!(self, Style::Camel | Style::Pascal)
+    }
+}
+
+/// Ways Style::Random cases a word, each equally likely
+pub const RANDOM_STYLES: [Style; 3] = [Style::Lower, Style::Capitalized, Style::Upper];
+
+/// How the letters of each word are cased
+#[derive(Clone, Debug)]
+pub enum Case {
+    Style(Style, Language),
+    /// A mask whose random positions are uppercased with the given probability
+    Mask(CaseMask, f64, Language),
+}
+
+impl Case {
+    pub fn language(&self) -> Language {
+        match self {
+            Case::Style(_, language) | Case::Mask(_, _, language) => *language,
+        }
+    }
+
+    /// How word `n` of a passphrase is cased, which differs from the rest
+    /// only for the first word of camel case
+    pub fn for_position(&self, n: usize) -> Case {
+        match self {
+            Case::Style(Style::Camel, language) if n == 0 => Case::Style(Style::Lower, *language),
+            _ => self.clone(),
+        }
+    }
+
+    pub fn apply<R: Rng>(&self, word: &mut String, rng: &mut R) {
+        let cased: String = match self {
+            Case::Style(Style::None, _) => return,
+            Case::Style(Style::Lower, language) => word.chars().map(|c| language.lower(c)).collect(),
+            Case::Style(Style::Capitalized | Style::Camel | Style::Pascal, language) => language.capitalize(word),
+            Case::Style(Style::Upper, language) => word.chars().map(|c| language.upper(c)).collect(),
+            Case::Style(Style::Random, language) => {
+                let style = RANDOM_STYLES[rng.gen_range(0..RANDOM_STYLES.len())];
+                return Case::Style(style, *language).apply(word, rng);
+            }
+            Case::Style(Style::Alternating, language) => word.chars()
+                .enumerate()
+                .map(|(i, c)| language.cased(c, i % 2 == 1))
+                .collect(),
+            Case::Mask(mask, prob, language) => word.chars()
+                .enumerate()
+                .map(|(i, c)| match mask.at(i) {
+                    MaskPos::Upper => language.upper(c),
+                    MaskPos::Lower => language.lower(c),
+                    MaskPos::Random => language.cased(c, rng.gen_bool(*prob)),
+                    MaskPos::Keep => c.to_string(),
+                })
+                .collect(),
+        };
+        *word = cased;
+    }
+
+    /// What the character at `i` of `word` can become once the word is
+    /// cased, with probabilities. Casing can turn one character into
+    /// several, as ß into SS.
+    pub fn outcomes(&self, word: &str, i: usize) -> Vec<(String, f64)> {
+        let c = match word.chars().nth(i) {
+            Some(c) => c,
+            None => return Vec::new(),
+        };
+
+        let fixed = match self {
+            Case::Style(Style::Lower, language) => language.lower(c),
+            Case::Style(Style::Capitalized | Style::Camel | Style::Pascal, language) => {
+                if i < language.capital_len(word) { language.upper(c) } else { c.to_string() }
+            }
+            Case::Style(Style::Upper, language) => language.upper(c),
+            Case::Style(Style::Alternating, language) => language.cased(c, i % 2 == 1),
+            Case::Style(Style::Random, language) => {
+                let share = 1.0 / RANDOM_STYLES.len() as f64;
+                let capitalized = if i < language.capital_len(word) { language.upper(c) } else { c.to_string() };
+                return vec![(language.lower(c), share), (capitalized, share), (language.upper(c), share)];
+            }
+            Case::Style(Style::None, _) => c.to_string(),
+            Case::Mask(mask, prob, language) => match mask.at(i) {
+                MaskPos::Upper => language.upper(c),
+                MaskPos::Lower => language.lower(c),
+                MaskPos::Keep => c.to_string(),
+                MaskPos::Random => return vec![
+                    (language.upper(c), *prob),
+                    (language.lower(c), 1.0 - prob),
+                ],
+            },
+        };
+        vec![(fixed, 1.0)]
+    }
+
+    /// Whether casing `word` could put any of `chars` in it
+    pub fn may_produce(&self, word: &str, chars: &[char]) -> bool {
+        (0..word.chars().count())
+            .any(|i| self.outcomes(word, i).iter().any(|(s, _)| s.chars().any(|c| chars.contains(&c))))
+    }
+
+    /// Bits of entropy added to each word. Only random styles and random mask
+    /// positions count, and only as many as the least caseable word in the
+    /// list provides.
+    pub fn entropy(&self, list: &[String]) -> f64 {
+        match self {
+            Case::Style(Style::Random, language) => {
+                // Styles that give the same word, as all do for "42" and
+                // capitalized and upper do for "a", pool their share
+                let share = 1.0 / RANDOM_STYLES.len() as f64;
+                list.iter()
+                    .map(|w| {
+                        let mut cased: Vec<String> = RANDOM_STYLES.iter().map(|&style| {
+                            let mut word = w.clone();
+                            Case::Style(style, *language).apply(&mut word, &mut rand::thread_rng());
+                            word
+                        }).collect();
+                        cased.sort();
+                        let mut counts = Vec::new();
+                        let mut cased = cased.into_iter().peekable();
+                        while let Some(word) = cased.next() {
+                            let mut count = 1;
+                            while cased.next_if(|next| *next == word).is_some() {
+                                count += 1;
+                            }
+                            counts.push(count);
+                        }
+                        counts.iter().map(|&n| -(n as f64 * share) * (n as f64 * share).log2()).sum::<f64>()
+                    })
+                    .fold(f64::INFINITY, f64::min)
+                    .min((RANDOM_STYLES.len() as f64).log2())
+            }
+            Case::Style(..) => 0.0,
+            Case::Mask(mask, prob, language) => {
+                let random = list.iter()
+                    .map(|w| w.chars()
+                        .enumerate()
+                        .filter(|(i, c)| mask.at(*i) == MaskPos::Random
+                            && language.upper(*c) != language.lower(*c))
+                        .count())
+                    .min()
+                    .unwrap_or(0);
+
+                random as f64 * binary_entropy(*prob)
+            }
+        }
+    }
+}
+
+/// Entropy in bits of a choice made with probability `p`
+pub fn binary_entropy(p: f64) -> f64 {
+    if p <= 0.0 || p >= 1.0 {
+        0.0
+    } else {
+        -(p * p.log2() + (1.0 - p) * (1.0 - p).log2())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Language;
+
+    #[test]
+    fn unicode() {
+        assert_eq!(Language::Unicode.upper('\u{df}'), "SS");
+        assert_eq!(Language::Unicode.upper('i'), "I");
+        assert_eq!(Language::Unicode.lower('I'), "i");
+        assert_eq!(Language::Unicode.lower('\u{130}'), "i\u{307}");
+        assert_eq!(Language::Unicode.capitalize("stra\u{df}e"), "Stra\u{df}e");
+        assert_eq!(Language::Unicode.capitalize("\u{df}"), "SS");
+        assert_eq!(Language::Unicode.capitalize("e\u{301}te\u{301}"), "E\u{301}te\u{301}");
+        assert_eq!(Language::Unicode.capitalize("ijsland"), "Ijsland");
+    }
+
+    #[test]
+    fn turkish() {
+        assert_eq!(Language::Turkish.upper('i'), "\u{130}");
+        assert_eq!(Language::Turkish.upper('\u{131}'), "I");
+        assert_eq!(Language::Turkish.lower('I'), "\u{131}");
+        assert_eq!(Language::Turkish.lower('\u{130}'), "i");
+        assert_eq!(Language::Turkish.capitalize("istanbul"), "\u{130}stanbul");
+        assert_eq!(Language::Turkish.capitalize("\u{131}s\u{131}k"), "Is\u{131}k");
+    }
+
+    #[test]
+    fn dutch() {
+        assert_eq!(Language::Dutch.upper('i'), "I");
+        assert_eq!(Language::Dutch.lower('I'), "i");
+        assert_eq!(Language::Dutch.capitalize("ijsland"), "IJsland");
+        assert_eq!(Language::Dutch.capitalize("IJsland"), "IJsland");
+        assert_eq!(Language::Dutch.capitalize("insect"), "Insect");
+        assert_eq!(Language::Dutch.capitalize("i"), "I");
+    }
+}
@@ -0,0 +1,66 @@
+use anyhow::{bail, Result};
+use rand::RngCore;
+
+use passphrs::transform::Transform;
+use passphrs::{join_parts, Part};
+
+/// Separates a token from its check segment
+pub const SEPARATOR: char = '_';
+/// Length of the base62 check segment, enough to hold any CRC32
+const LENGTH: usize = 6;
+const BASE62: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// The base62-encoded CRC32 of `token`, zero padded to a fixed length
+pub fn segment(token: &str) -> String {
+    let mut crc = crc32fast::hash(token.as_bytes());
+    let mut digits = vec![b'0'; LENGTH];
+    for digit in digits.iter_mut().rev() {
+        *digit = BASE62[(crc % 62) as usize];
+        crc /= 62;
+    }
+    String::from_utf8(digits).unwrap()
+}
+
+/// Ends the passphrase with the check segment of everything before it, as
+/// the last step of the pipeline
+#[derive(Debug)]
+pub struct Checksum;
+
+impl Transform for Checksum {
+    fn apply(&self, parts: &mut Vec<Part>, _rng: &mut dyn RngCore) {
+        let check = segment(&join_parts(parts));
+        parts.push(Part::Literal(format!("{}{}", SEPARATOR, check)));
+    }
+
+    fn bits(&self, _list: &[String], _length: usize) -> f64 {
+        0.0
+    }
+}
+
+/// Whether the check segment at the end of `token` matches the rest of it
+pub fn verify(token: &str) -> bool {
+    match token.rsplit_once(SEPARATOR) {
+        Some((body, check)) => check.len() == LENGTH && segment(body) == check,
+        None => false,
+    }
+}
+
+/// Checks a token given as an argument, or read from stdin so that it stays
+/// out of the shell history
+pub fn verify_command(token: Option<&String>) -> Result<()> {
+    let token = match token {
+        Some(token) => token.clone(),
+        None => {
+            let mut line = String::new();
+            std::io::stdin().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+
+    if verify(&token) {
+        println!("Checksum valid");
+        Ok(())
+    } else {
+        bail!("Checksum invalid: the token is mistyped, truncated or has no check segment")
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs::File;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, bail, Context, Result};
+use ::clipboard::{ClipboardContext, ClipboardProvider};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::ArgEnum;
+
+/// What to do when the session has no clipboard to copy to
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Fallback {
+    /// Fail, as without a fallback
+    None,
+    /// Write the passphrase to stdout, as --print does
+    Print,
+    /// Hand the passphrase to Windows' clip.exe, which also works from WSL
+    ClipExe,
+}
+
+/// The system clipboard, reached through X11 (or the platform's native API)
+/// or, in a Wayland session, through the wl-clipboard tools. Over SSH, the
+/// clipboard of the local terminal emulator can be set with OSC 52.
+pub enum Clipboard {
+    Native(ClipboardContext),
+    Wayland,
+    /// The controlling terminal, written to directly so piped stdout is
+    /// never involved
+    Osc52(File),
+    /// Windows' clip.exe, which can set the clipboard but not read it
+    ClipExe,
+}
+
+/// The kind of session, if this is a Windows console nobody is sitting at,
+/// such as a CI job or an SSH login. Its clipboard belongs to a session no
+/// one sees, or cannot be opened at all.
+#[cfg(windows)]
+pub fn unattended() -> Option<&'static str> {
+    use std::io::IsTerminal;
+
+    if std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_CLIENT").is_some() {
+        Some("an SSH session")
+    } else if std::env::var_os("CI").is_some() {
+        Some("a CI job")
+    } else if !std::io::stdin().is_terminal() && !std::io::stderr().is_terminal() {
+        Some("a non-interactive console")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(windows))]
+pub fn unattended() -> Option<&'static str> {
+    None
+}
+
+/// Rings the bell of the controlling terminal, so that it sounds with
+/// stderr redirected and from a detached process. Does nothing without one.
+pub fn bell() {
+    let terminal = if cfg!(windows) { "CONOUT$" } else { "/dev/tty" };
+    if let Ok(mut tty) = std::fs::OpenOptions::new().write(true).open(terminal) {
+        let _ = tty.write_all(b"\x07");
+    }
+}
+
+impl Clipboard {
+    /// Connects to the clipboard of the current session. Wayland is used
+    /// when WAYLAND_DISPLAY is set and wl-copy can be run, so that sessions
+    /// without XWayland work.
+    pub fn new() -> Result<Clipboard> {
+        let wayland = std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty());
+        if wayland && Command::new("wl-copy").arg("--version").output().is_ok() {
+            return Ok(Clipboard::Wayland);
+        }
+
+        match ClipboardProvider::new() {
+            Ok(ctx) => Ok(Clipboard::Native(ctx)),
+            Err(err) if wayland => bail!("Could not open the clipboard ({}). Install wl-clipboard for Wayland support.", err),
+            Err(err) => bail!("Could not open the clipboard: {}", err),
+        }
+    }
+
+    /// Connects as `new` does, unless the session is an unattended Windows
+    /// console or the clipboard cannot be opened. Then `fallback` decides:
+    /// clip.exe, or None for the caller to print the passphrase instead.
+    pub fn with_fallback(fallback: Fallback) -> Result<Option<Clipboard>> {
+        let opened = match unattended() {
+            Some(session) => Err(anyhow!(
+                "This is {}, whose clipboard no one would see. Pass --clipboard-fallback print or clip-exe, or --print.",
+                session)),
+            None => Clipboard::new(),
+        };
+        match (opened, fallback) {
+            (Ok(clipboard), _) => Ok(Some(clipboard)),
+            (Err(err), Fallback::None) => Err(err),
+            (Err(err), Fallback::Print) => {
+                eprintln!("{:#}", err);
+                eprintln!("Printing the passphrase instead");
+                Ok(None)
+            }
+            (Err(_), Fallback::ClipExe) => Ok(Some(Clipboard::ClipExe)),
+        }
+    }
+
+    /// Sets the clipboard through the terminal emulator with the OSC 52
+    /// escape sequence, wrapped for passthrough inside tmux
+    pub fn osc52() -> Result<Clipboard> {
+        let tty = std::fs::OpenOptions::new().write(true).open("/dev/tty")
+            .context("OSC 52 needs a controlling terminal")?;
+        Ok(Clipboard::Osc52(tty))
+    }
+
+    fn write_osc52(tty: &mut File, contents: &str) -> Result<()> {
+        let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(contents));
+        let sequence = if std::env::var_os("TMUX").is_some() {
+            format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+        } else {
+            sequence
+        };
+        tty.write_all(sequence.as_bytes())?;
+        tty.flush()?;
+        Ok(())
+    }
+
+    pub fn get_contents(&mut self) -> Result<String> {
+        match self {
+            // Few terminals answer OSC 52 queries, and those that do usually
+            // ask the user first
+            Clipboard::Osc52(_) => bail!("The terminal clipboard cannot be read"),
+            Clipboard::ClipExe => bail!("clip.exe cannot read the clipboard"),
+            Clipboard::Native(ctx) => ctx.get_contents().map_err(|err| anyhow!("{}", err)),
+            Clipboard::Wayland => {
+                let output = Command::new("wl-paste").arg("--no-newline").output()
+                    .context("Could not run wl-paste")?;
+                if !output.status.success() {
+                    bail!("wl-paste failed ({})", output.status);
+                }
+                Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+            }
+        }
+    }
+
+    /// Puts `contents` on the clipboard. On Wayland they are handed to
+    /// wl-copy on stdin, never on argv, and likewise to clip.exe.
+    pub fn set_contents(&mut self, contents: String) -> Result<()> {
+        match self {
+            Clipboard::Osc52(tty) => Clipboard::write_osc52(tty, &contents),
+            Clipboard::ClipExe => {
+                let mut child = Command::new("clip.exe")
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .context("Could not run clip.exe")?;
+                // clip.exe reads the console code page unless the input is
+                // UTF-16 with a byte order mark
+                let utf16: Vec<u8> = std::iter::once(0xfeff)
+                    .chain(contents.encode_utf16())
+                    .flat_map(u16::to_le_bytes)
+                    .collect();
+                child.stdin.take().unwrap().write_all(&utf16)?;
+                let status = child.wait()?;
+                if !status.success() {
+                    bail!("clip.exe failed ({})", status);
+                }
+                Ok(())
+            }
+            Clipboard::Native(ctx) => ctx.set_contents(contents).map_err(|err| anyhow!("{}", err)),
+            Clipboard::Wayland => {
+                let mut child = Command::new("wl-copy")
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .context("Could not run wl-copy")?;
+                child.stdin.take().unwrap().write_all(contents.as_bytes())?;
+                let status = child.wait()?;
+                if !status.success() {
+                    bail!("wl-copy failed ({})", status);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn clear(&mut self) -> Result<()> {
+        match self {
+            Clipboard::Native(_) | Clipboard::Osc52(_) | Clipboard::ClipExe => self.set_contents(String::new()),
+            Clipboard::Wayland => {
+                let status = Command::new("wl-copy").arg("--clear").status().context("Could not run wl-copy")?;
+                if !status.success() {
+                    bail!("wl-copy --clear failed ({})", status);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Takes `ours` back out of the clipboard once it is no longer needed,
+    /// putting back `previous` if given. Anything copied since is left alone;
+    /// a clipboard that cannot be read back, as over OSC 52 or through
+    /// clip.exe, is cleared
+    /// regardless. Failures are reported but not fatal.
+    pub fn release(&mut self, ours: &str, previous: Option<String>) {
+        match (self.get_contents(), previous) {
+            (Ok(current), _) if current != ours => {
+                eprintln!("The clipboard has changed since the passphrase was copied, leaving it alone");
+            }
+            (_, Some(previous)) if !previous.is_empty() => {
+                if let Err(err_) = self.set_contents(previous) {
+                    eprintln!("Could not restore clipboard contents: {}", err_);
+                }
+            }
+            _ => {
+                if let Err(err_) = self.clear() {
+                    eprintln!("Could not clear clipboard contents: {}", err_);
+                }
+            }
+        }
+    }
+}
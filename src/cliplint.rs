@@ -0,0 +1,68 @@
+use std::io::{BufRead, IsTerminal, Write};
+
+use anyhow::{bail, Result};
+
+/// Prefixes of well known API token formats
+const TOKEN_PREFIXES: [&str; 8] = ["ghp_", "gho_", "github_pat_", "glpat-", "xoxb-", "xoxp-", "sk-", "AKIA"];
+/// Shortest string considered for the high-entropy check
+const MIN_SECRET_LEN: usize = 16;
+/// Shannon entropy per character above which a string looks random
+const MIN_BITS_PER_CHAR: f64 = 3.5;
+
+fn bits_per_char(s: &str) -> f64 {
+    let mut counts = std::collections::HashMap::new();
+    for c in s.chars() {
+        *counts.entry(c).or_insert(0usize) += 1;
+    }
+    let len = s.chars().count() as f64;
+    counts.values()
+        .map(|&n| {
+            let p = n as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Describes why `contents` look like a secret, if they do
+pub fn looks_like_secret(contents: &str) -> Option<&'static str> {
+    let trimmed = contents.trim();
+    if trimmed.contains("-----BEGIN") && trimmed.contains("PRIVATE KEY") {
+        return Some("a private key");
+    }
+    if TOKEN_PREFIXES.iter().any(|prefix| trimmed.starts_with(prefix)) {
+        return Some("an API token");
+    }
+
+    let single_token = !trimmed.chars().any(char::is_whitespace);
+    let classes = [char::is_lowercase, char::is_uppercase, |c: char| c.is_ascii_digit()]
+        .iter()
+        .filter(|class| trimmed.chars().any(**class))
+        .count();
+    if single_token && trimmed.chars().count() >= MIN_SECRET_LEN && classes >= 2
+        && bits_per_char(trimmed) >= MIN_BITS_PER_CHAR {
+        return Some("a high-entropy string");
+    }
+    None
+}
+
+/// Asks before replacing clipboard contents that look like another secret.
+/// Without a terminal to ask on, refuses unless `force` is set.
+pub fn confirm_overwrite(contents: &str, force: bool) -> Result<()> {
+    let reason = match looks_like_secret(contents) {
+        Some(reason) if !force => reason,
+        _ => return Ok(()),
+    };
+
+    if !std::io::stdin().is_terminal() {
+        bail!("The clipboard holds what looks like {}. Use --force to overwrite it.", reason);
+    }
+
+    eprint!("The clipboard holds what looks like {}. Overwrite it? [y/N] ", reason);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        bail!("Left the clipboard unchanged");
+    }
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::io::IsTerminal;
+
+use clap::ArgEnum;
+
+use passphrs::Part;
+
+const WORD: &str = "36";
+const DIM: &str = "2";
+const SALT: &str = "1;33";
+const WEAK: &str = "31";
+const FAIR: &str = "33";
+const STRONG: &str = "32";
+
+/// Width of the strength bar in characters
+const BAR_WIDTH: usize = 32;
+/// Entropy at which the strength bar is full
+const BAR_MAX_BITS: f64 = 128.0;
+
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether ANSI escapes should be written to stdout. In auto mode color is
+    /// used only on a terminal and only if NO_COLOR is unset or empty.
+    pub fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+pub fn paint(text: &str, code: &str, enabled: bool) -> String {
+    if enabled && !text.is_empty() {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Marker shown in place of empty separators when boundaries are requested
+const BOUNDARY: &str = "\u{b7}";
+
+/// Renders a passphrase with words, separators and salt tinted differently.
+/// If `boundaries` is set, empty separators are shown as a faint marker.
+pub fn paint_parts(parts: &[Part], boundaries: bool, enabled: bool) -> String {
+    parts.iter()
+        .map(|part| match part {
+            Part::Word(w, _) => paint(w, WORD, enabled),
+            Part::Separator(s) if s.is_empty() && boundaries => paint(BOUNDARY, DIM, enabled),
+            Part::Separator(s) => paint(s, DIM, enabled),
+            Part::Salt(s) => paint(s, SALT, enabled),
+            Part::Literal(s) => paint(s, DIM, enabled),
+        })
+        .collect()
+}
+
+/// Renders a bar filled in proportion to `entropy`, tinted by strength
+/// "weak", "fair" or "strong"
+pub fn strength(entropy: f64) -> &'static str {
+    if entropy < 50.0 {
+        "weak"
+    } else if entropy < 80.0 {
+        "fair"
+    } else {
+        "strong"
+    }
+}
+
+pub fn strength_bar(entropy: f64, enabled: bool) -> String {
+    let filled = ((entropy / BAR_MAX_BITS).clamp(0.0, 1.0) * BAR_WIDTH as f64).round() as usize;
+    let label = strength(entropy);
+    let code = match label {
+        "weak" => WEAK,
+        "fair" => FAIR,
+        _ => STRONG,
+    };
+
+    format!(
+        "[{}{}] {}",
+        paint(&"#".repeat(filled), code, enabled),
+        "-".repeat(BAR_WIDTH - filled),
+        paint(label, code, enabled),
+    )
+}
+
+/// Well known secrets to compare a passphrase against, with their entropy
+const REFERENCES: [(&str, f64); 3] = [
+    ("4-digit PIN", 13.29),
+    ("8-char random password", 52.56),
+    ("12-word BIP39 mnemonic", 128.0),
+];
+
+const OWN_LABEL: &str = "This passphrase";
+
+/// Renders one bar per reference point plus `entropy`, sorted by strength.
+/// Bars are linear in bits, so logarithmic in the number of guesses.
+pub fn comparison(entropy: f64, enabled: bool) -> Vec<String> {
+    let mut rows: Vec<(&str, f64)> = REFERENCES.to_vec();
+    rows.push((OWN_LABEL, entropy));
+    rows.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    let max = rows.last().map_or(BAR_MAX_BITS, |row| row.1).max(1.0);
+    let label_width = rows.iter().map(|row| row.0.len()).max().unwrap_or(0);
+    rows.iter()
+        .map(|(label, bits)| {
+            let filled = ((bits / max).clamp(0.0, 1.0) * BAR_WIDTH as f64).round() as usize;
+            let bar = format!("{}{}", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled));
+            let line = format!("{:<width$} [{}] {:6.1} bits", label, bar, bits, width = label_width);
+            if *label == OWN_LABEL { paint(&line, STRONG, enabled) } else { line }
+        })
+        .collect()
+}
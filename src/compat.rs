@@ -0,0 +1,190 @@
+use clap::ArgEnum;
+use thiserror::Error;
+
+/// Tools whose command line conventions can be emulated with `--compat`
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Compat {
+    Xkcdpass,
+    Pwgen,
+}
+
+#[derive(Debug, Error)]
+pub enum CompatError {
+    #[error("{0}, expected xkcdpass or pwgen")]
+    UnknownMode(String),
+    #[error("{0} requires a value")]
+    MissingValue(String),
+    #[error("{0} flag '{1}' has no passphrs equivalent")]
+    Unsupported(&'static str, String),
+}
+
+/// Rewrites the arguments following `--compat <MODE>` from the emulated
+/// tool's conventions into native passphrs flags. Arguments are returned
+/// unchanged if `--compat` is not present.
+pub fn translate(args: Vec<String>) -> Result<Vec<String>, CompatError> {
+    let mut mode = None;
+    let mut rest = Vec::new();
+    let mut iter = args.into_iter();
+    let mut out: Vec<String> = iter.next().into_iter().collect();
+
+    while let Some(arg) = iter.next() {
+        if mode.is_some() {
+            rest.push(arg);
+        } else if arg == "--compat" {
+            let value = iter.next().ok_or(CompatError::MissingValue(arg))?;
+            mode = Some(Compat::from_str(&value, true).map_err(CompatError::UnknownMode)?);
+        } else if let Some(value) = arg.strip_prefix("--compat=") {
+            mode = Some(Compat::from_str(value, true).map_err(CompatError::UnknownMode)?);
+        } else {
+            out.push(arg);
+        }
+    }
+
+    let mode = match mode {
+        Some(mode) => mode,
+        None => return Ok(out),
+    };
+
+    out.push("--compat".to_string());
+    out.push(format!("{:?}", mode).to_ascii_lowercase());
+    match mode {
+        Compat::Xkcdpass => translate_xkcdpass(rest, &mut out)?,
+        Compat::Pwgen => translate_pwgen(rest, &mut out)?,
+    }
+
+    Ok(out)
+}
+
+/// Splits `--flag=value` into its flag and inline value
+fn split_flag(arg: &str) -> (&str, Option<&str>) {
+    match arg.split_once('=') {
+        Some((flag, value)) if flag.starts_with("--") => (flag, Some(value)),
+        _ => (arg, None),
+    }
+}
+
+fn translate_xkcdpass(args: Vec<String>, out: &mut Vec<String>) -> Result<(), CompatError> {
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_flag(&arg);
+        let native = match flag {
+            "-n" | "--numwords" => "--length",
+            "-d" | "--delimiter" => "--separator",
+            "-w" | "--wordfile" => "--path",
+            "--min" => "--min-word-len",
+            "--max" => "--max-word-len",
+            "-C" | "--case" => "--case",
+            "-c" | "--count" => "--count",
+            _ => return Err(CompatError::Unsupported("xkcdpass", arg)),
+        };
+
+        let value = match inline {
+            Some(value) => value.to_string(),
+            None => iter.next().ok_or_else(|| CompatError::MissingValue(flag.to_string()))?,
+        };
+        let value = if native == "--case" {
+            // xkcdpass's random and alternating case whole words where
+            // passphrs's case letters, but both are random-looking casing
+            // that adds none of xkcdpass's structure
+            match value.as_str() {
+                "as-is" => "none",
+                "lower" => "lower",
+                "capitalize" => "capitalized",
+                "upper" => "upper",
+                "random" => "random",
+                "alternating" => "alternating",
+                _ => return Err(CompatError::Unsupported("xkcdpass", format!("{} {}", flag, value))),
+            }.to_string()
+        } else {
+            value
+        };
+
+        // xkcdpass prints its passphrases one per line
+        if native == "--count" {
+            out.extend(["--print".to_string(), "--layout".to_string(), "lines".to_string()]);
+        }
+        out.push(native.to_string());
+        out.push(value);
+    }
+
+    Ok(())
+}
+
+/// Splits pwgen's combined short flags, as `-cny` into `-c -n -y`. The rest
+/// of a cluster after `-N` is its value, as in `-N5`.
+fn split_short(args: Vec<String>) -> Vec<String> {
+    let mut split = Vec::new();
+    for arg in args {
+        match arg.strip_prefix('-') {
+            Some(cluster) if cluster.len() > 1 && !cluster.starts_with('-') => {
+                for (i, c) in cluster.char_indices() {
+                    split.push(format!("-{}", c));
+                    if c == 'N' {
+                        let value = &cluster[i + c.len_utf8()..];
+                        if !value.is_empty() {
+                            split.push(value.to_string());
+                        }
+                        break;
+                    }
+                }
+            }
+            _ => split.push(arg),
+        }
+    }
+    split
+}
+
+fn translate_pwgen(args: Vec<String>, out: &mut Vec<String>) -> Result<(), CompatError> {
+    let mut numerals = None;
+    let mut symbols = false;
+    let mut count = None;
+    let mut positional = Vec::new();
+    let mut iter = split_short(args).into_iter();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = split_flag(&arg);
+        match flag {
+            "-c" | "--capitalize" => out.extend(["--case".to_string(), "capitalized".to_string()]),
+            "-A" | "--no-capitalize" => out.extend(["--case".to_string(), "lower".to_string()]),
+            "-n" | "--numerals" => numerals = Some(true),
+            "-0" | "--no-numerals" => numerals = Some(false),
+            "-y" | "--symbols" => symbols = true,
+            "-1" => out.extend(["--layout".to_string(), "lines".to_string()]),
+            "-C" => out.extend(["--layout".to_string(), "columns".to_string()]),
+            "-N" | "--num-passwords" => count = Some(match inline {
+                Some(value) => value.to_string(),
+                None => iter.next().ok_or_else(|| CompatError::MissingValue(flag.to_string()))?,
+            }),
+            // passphrs always uses a CSPRNG
+            "-s" | "--secure" => {}
+            _ if !arg.starts_with('-') && positional.len() < 2 => positional.push(arg),
+            _ => return Err(CompatError::Unsupported("pwgen", arg)),
+        }
+    }
+
+    // pwgen's [pw_length] [num_pw]. A passphrase cannot be held to an exact
+    // length, so pwgen's becomes a minimum.
+    let mut positional = positional.into_iter();
+    if let Some(length) = positional.next() {
+        out.extend(["--min-chars".to_string(), length]);
+    }
+    if let Some(count) = positional.next().or(count) {
+        // pwgen prints its passwords rather than copying one
+        out.extend(["--count".to_string(), count, "--print".to_string()]);
+    }
+
+    let mut salt_chars = String::new();
+    if numerals != Some(false) {
+        salt_chars += "0123456789";
+    }
+    if symbols {
+        salt_chars += "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+    }
+
+    if salt_chars.is_empty() {
+        out.extend(["--sl".to_string(), "0".to_string()]);
+    } else {
+        out.extend(["--sc".to_string(), salt_chars]);
+    }
+
+    Ok(())
+}
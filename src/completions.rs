@@ -0,0 +1,18 @@
+use anyhow::Result;
+use clap::IntoApp;
+use clap_complete::Shell;
+
+use crate::{config, Cli};
+
+/// Prints a completion script for `shell`. Bundled word list names come from
+/// the --list values; profile names are read from the config file now, so
+/// regenerate the script after adding a profile.
+pub fn completions_command(shell: Shell) -> Result<()> {
+    let profiles: Vec<String> = config::load().map(|config| config.profiles.into_keys().collect()).unwrap_or_default();
+    let mut app = Cli::into_app();
+    if !profiles.is_empty() {
+        app = app.mut_arg("profile", |arg| arg.possible_values(profiles.iter().map(String::as_str)));
+    }
+    clap_complete::generate(shell, &mut app, "passphrs", &mut std::io::stdout());
+    Ok(())
+}
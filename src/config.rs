@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{ArgEnum, ArgMatches};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use passphrs::case::Style;
+use passphrs::constraint::CharClass;
+use passphrs::wordlist::List;
+
+use crate::clipboard::Fallback;
+use crate::{parse_wait, Cli};
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Settings that replace the built-in defaults, either at the top of the
+/// config file or saved under a name and selected with --profile. Missing
+/// settings are left alone.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Profile {
+    pub length: Option<usize>,
+    pub separator: Option<String>,
+    pub salt_length: Option<usize>,
+    pub salt_chars: Option<String>,
+    /// Case style, or the number it was before styles had names
+    #[serde(deserialize_with = "case_name")]
+    pub case: Option<String>,
+    /// Name of a bundled word list, as given to --list
+    pub list: Option<String>,
+    /// Custom word list file, as given to --path
+    pub path: Option<String>,
+    /// Character class, as given to --must-end-with
+    pub must_end_with: Option<String>,
+    /// As given to --clipboard-fallback, e.g. print for a CI runner
+    pub clipboard_fallback: Option<String>,
+    /// Longest --wait allowed, as a duration such as "2m". Also caps --wait 0.
+    pub max_wait: Option<String>,
+}
+
+/// The contents of config.toml: defaults for every run, then named profiles
+/// as `[profiles.NAME]` tables. Command line flags override both, and a
+/// profile overrides the defaults.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    #[serde(flatten)]
+    pub defaults: Profile,
+    /// Words lint treats as known to an attacker, such as product names or
+    /// office locations
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub terms: Vec<String>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Where the config file lives: $XDG_CONFIG_HOME/passphrs, ~/.config/passphrs
+/// or the platform's equivalent, such as %APPDATA%\passphrs on Windows
+pub fn path() -> Result<PathBuf> {
+    Ok(dirs::config_dir()
+        .ok_or_else(|| anyhow!("Could not find a configuration directory"))?
+        .join("passphrs")
+        .join(CONFIG_FILE))
+}
+
+/// Reads the config file, which need not exist
+pub fn load() -> Result<Config> {
+    let path = path()?;
+    match std::fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text).with_context(|| format!("Could not parse {}", path.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(err) => Err(err).with_context(|| format!("Could not read {}", path.display())),
+    }
+}
+
+/// Saves `profile` as `name`, replacing any profile of that name. Returns
+/// where it was written.
+pub fn save_profile(name: &str, profile: Profile) -> Result<PathBuf> {
+    let mut config = load()?;
+    config.profiles.insert(name.to_string(), profile);
+
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, toml::to_string_pretty(&config)?)
+        .with_context(|| format!("Could not write {}", path.display()))?;
+    Ok(path)
+}
+
+impl Config {
+    pub fn profile(&self, name: &str) -> Result<&Profile> {
+        match self.profiles.get(name) {
+            Some(profile) => Ok(profile),
+            None if self.profiles.is_empty() => bail!("No profile named {}, and {} defines none", name, CONFIG_FILE),
+            None => bail!("No profile named {}. Known profiles: {}",
+                name, self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")),
+        }
+    }
+}
+
+/// Reads `case = 1` from older config files as well as `case = "lower"`
+fn case_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Case {
+        Number(u64),
+        Name(String),
+    }
+    Ok(Some(match Case::deserialize(deserializer)? {
+        Case::Number(n) => n.to_string(),
+        Case::Name(name) => name,
+    }))
+}
+
+fn parse_arg<T: ArgEnum>(setting: &str, value: &str) -> Result<T> {
+    T::from_str(value, true).map_err(|_| anyhow!("Invalid {} in {}: {}", setting, CONFIG_FILE, value))
+}
+
+impl Profile {
+    /// Ids of the settings the profile changes
+    pub fn settings(&self) -> Vec<&'static str> {
+        [
+            ("length", self.length.is_some()),
+            ("separator", self.separator.is_some()),
+            ("salt-length", self.salt_length.is_some()),
+            ("salt-chars", self.salt_chars.is_some()),
+            ("case", self.case.is_some()),
+            ("list", self.list.is_some()),
+            ("path", self.path.is_some()),
+            ("must-end-with", self.must_end_with.is_some()),
+            ("clipboard-fallback", self.clipboard_fallback.is_some()),
+            ("max-wait", self.max_wait.is_some()),
+        ]
+        .iter()
+        .filter(|(_, set)| *set)
+        .map(|(id, _)| *id)
+        .collect()
+    }
+
+    /// Applies the profile to every setting that was not given explicitly,
+    /// returning the ids of those it set
+    pub fn apply(&self, cli: &mut Cli, matches: &ArgMatches) -> Result<Vec<&'static str>> {
+        let unset = |id: &str| matches.occurrences_of(id) == 0;
+        if let (Some(length), true) = (self.length, unset("length")) { cli.length = length; }
+        if let (Some(separator), true) = (&self.separator, unset("separator")) { cli.separator = separator.clone(); }
+        if let (Some(length), true) = (self.salt_length, unset("salt-length")) { cli.salt_length = length; }
+        if let (Some(chars), true) = (&self.salt_chars, unset("salt-chars")) { cli.salt_chars = chars.clone(); }
+        if let (Some(case), true) = (&self.case, unset("case")) { cli.case = parse_arg::<Style>("case", case)?; }
+        // A word list given anywhere replaces one given at a lower level,
+        // whether bundled or a file
+        let list_unset = unset("list") && unset("path");
+        if let (Some(list), true) = (&self.list, list_unset) {
+            cli.list = parse_arg::<List>("list", list)?;
+            cli.path = None;
+        }
+        if let (Some(path), true) = (&self.path, list_unset) { cli.path = Some(path.clone()); }
+        if let (Some(class), true) = (&self.must_end_with, unset("must-end-with")) {
+            cli.must_end_with = Some(parse_arg::<CharClass>("must-end-with", class)?);
+        }
+        if let (Some(fallback), true) = (&self.clipboard_fallback, unset("clipboard-fallback")) {
+            cli.clipboard_fallback = parse_arg::<Fallback>("clipboard-fallback", fallback)?;
+        }
+        // Not a flag, so nothing on the command line can loosen it
+        if let Some(wait) = &self.max_wait {
+            cli.max_wait = Some(parse_wait(wait).map_err(|e| anyhow!("Invalid max-wait in {}: {}", CONFIG_FILE, e))?);
+        }
+        Ok(self.settings())
+    }
+}
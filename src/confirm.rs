@@ -0,0 +1,24 @@
+use std::io::{BufRead, IsTerminal, Write};
+
+use anyhow::{bail, Result};
+
+/// Asks before writing a new secret somewhere with lasting side effects,
+/// describing only the destination. Without a terminal to ask on, refuses
+/// unless `yes` is set.
+pub fn confirm_sink(destination: &str, yes: bool) -> Result<()> {
+    if yes {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        bail!("Not writing to {} without confirmation. Use --yes to skip it.", destination);
+    }
+
+    eprint!("Write the new passphrase to {}? [y/N] ", destination);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    if !answer.trim().eq_ignore_ascii_case("y") {
+        bail!("Nothing was generated or written");
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use clap::ArgEnum;
+
+use crate::case::{Case, Style, RANDOM_STYLES};
+
+pub const DIGITS: &str = "0123456789";
+/// Symbols sites accept almost everywhere
+pub const SYMBOLS: &str = "!#%+-=?@_";
+/// Passphrases sampled to estimate how many a Policy accepts
+const POLICY_SAMPLES: usize = 2000;
+
+/// A class of characters a passphrase may be required to start or end with
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum CharClass {
+    Letter,
+    Upper,
+    Lower,
+    Digit,
+    Alnum,
+    Symbol,
+}
+
+impl CharClass {
+    pub fn matches(self, c: char) -> bool {
+        match self {
+            CharClass::Letter => c.is_alphabetic(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Digit => c.is_ascii_digit(),
+            CharClass::Alnum => c.is_alphanumeric(),
+            CharClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+/// A site's password rules beyond how the passphrase starts and ends
+#[derive(Clone, Debug, Default)]
+pub struct Policy {
+    /// Classes that must each appear somewhere
+    pub require: Vec<CharClass>,
+    pub min_chars: Option<usize>,
+    pub max_chars: Option<usize>,
+}
+
+impl Policy {
+    pub fn is_empty(&self) -> bool {
+        self.require.is_empty() && self.min_chars.is_none() && self.max_chars.is_none()
+    }
+
+    pub fn allows(&self, phrase: &str) -> bool {
+        let len = phrase.chars().count();
+        self.require.iter().all(|class| phrase.chars().any(|c| class.matches(c)))
+            && self.min_chars.is_none_or(|min| len >= min)
+            && self.max_chars.is_none_or(|max| len <= max)
+    }
+
+    /// Share of passphrases from `sample` the policy accepts, estimated by
+    /// drawing a few thousand. Rejecting failures leaves `log2` of this many
+    /// fewer bits.
+    pub fn acceptance(&self, mut sample: impl FnMut() -> String) -> f64 {
+        if self.is_empty() {
+            return 1.0;
+        }
+        (0..POLICY_SAMPLES).filter(|_| self.allows(&sample())).count() as f64 / POLICY_SAMPLES as f64
+    }
+}
+
+fn matches(class: Option<CharClass>, c: char) -> bool {
+    class.is_none_or(|class| class.matches(c))
+}
+
+/// Whether `phrase` starts and ends with the required classes
+pub fn satisfied(phrase: &str, start: Option<CharClass>, end: Option<CharClass>) -> bool {
+    match (phrase.chars().next(), phrase.chars().last()) {
+        (Some(first), Some(last)) => matches(start, first) && matches(end, last),
+        _ => start.is_none() && end.is_none(),
+    }
+}
+
+/// Whether `phrase` meets the default Windows password complexity rules:
+/// characters from three of uppercase, lowercase, digits and symbols, and
+/// no case-insensitive copy of a `username` longer than two characters
+pub fn windows_complex(phrase: &str, username: Option<&str>) -> bool {
+    let classes = [CharClass::Upper, CharClass::Lower, CharClass::Digit, CharClass::Symbol]
+        .iter()
+        .filter(|class| phrase.chars().any(|c| class.matches(c)))
+        .count();
+    let contains_username = username
+        .filter(|name| name.chars().count() > 2)
+        .is_some_and(|name| phrase.to_lowercase().contains(&name.to_lowercase()));
+    classes >= 3 && !contains_username
+}
+
+/// Whether the first and last characters of `s` match `start` and `end`
+fn ends_match(s: &str, start: Option<CharClass>, end: Option<CharClass>) -> bool {
+    match (s.chars().next(), s.chars().last()) {
+        (Some(first), Some(last)) => matches(start, first) && matches(end, last),
+        _ => false,
+    }
+}
+
+/// Probability that a cased word satisfies `start` at its first character and
+/// `end` at its last. The two are independent unless the word is one
+/// character long.
+fn word_prob(word: &str, case: &Case, start: Option<CharClass>, end: Option<CharClass>) -> f64 {
+    // Random casing picks one style for the whole word, so its ends are
+    // cased alike
+    if let Case::Style(Style::Random, language) = case {
+        return RANDOM_STYLES.iter()
+            .map(|&style| word_prob(word, &Case::Style(style, *language), start, end))
+            .sum::<f64>() / RANDOM_STYLES.len() as f64;
+    }
+
+    let len = word.chars().count();
+    if len == 1 {
+        return case.outcomes(word, 0).iter()
+            .filter(|(s, _)| ends_match(s, start, end))
+            .map(|(_, p)| p)
+            .sum();
+    }
+
+    let prob = |i: usize, start, end| case.outcomes(word, i).iter()
+        .filter(|(s, _)| ends_match(s, start, end))
+        .map(|(_, p)| p)
+        .sum::<f64>();
+    prob(0, start, None) * prob(len.saturating_sub(1), None, end)
+}
+
+/// Probability that a passphrase generated with these settings satisfies the
+/// constraints. Rejecting failures leaves `log2` of this many fewer bits.
+pub fn acceptance(
+    list: &[String],
+    length: usize,
+    salt_length: usize,
+    salt_chars: &str,
+    case: &Case,
+    start: Option<CharClass>,
+    end: Option<CharClass>,
+) -> f64 {
+    let salt_chars: Vec<char> = salt_chars.chars().collect();
+    let salt_prob = |f: &dyn Fn(char) -> bool| if salt_chars.is_empty() {
+        0.0
+    } else {
+        salt_chars.iter().filter(|c| f(**c)).count() as f64 / salt_chars.len() as f64
+    };
+    let salt_end = salt_prob(&|c| matches(end, c));
+
+    // Without words the phrase is all salt, whose ends are independent
+    // unless it is a single character
+    if length == 0 {
+        return match salt_length {
+            // The empty phrase, which meets no constraint but the lack of one
+            0 => if start.is_none() && end.is_none() { 1.0 } else { 0.0 },
+            1 => salt_prob(&|c| matches(start, c) && matches(end, c)),
+            _ => salt_prob(&|c| matches(start, c)) * salt_end,
+        };
+    }
+    if list.is_empty() {
+        return 0.0;
+    }
+
+    let mean = |f: &dyn Fn(&String) -> f64| list.iter().map(f).sum::<f64>() / list.len() as f64;
+    let first = case.for_position(0);
+
+    // The phrase always starts with the first word. It ends with salt when
+    // the salt lands on the last of the `length` words.
+    if length == 1 {
+        if salt_length > 0 {
+            return mean(&|w| word_prob(w, &first, start, None)) * salt_end;
+        }
+        return mean(&|w| word_prob(w, &first, start, end));
+    }
+
+    let start_prob = mean(&|w| word_prob(w, &first, start, None));
+    let word_end = mean(&|w| word_prob(w, case, None, end));
+    let end_prob = if salt_length > 0 {
+        (salt_end + (length - 1) as f64 * word_end) / length as f64
+    } else {
+        word_end
+    };
+    start_prob * end_prob
+}
@@ -0,0 +1,41 @@
+use std::fs::File;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Saves the window title on the terminal's title stack
+const PUSH_TITLE: &str = "\x1b[22;0t";
+/// Restores the title saved by PUSH_TITLE
+const POP_TITLE: &str = "\x1b[23;0t";
+const TICK: Duration = Duration::from_secs(1);
+
+fn set_title(tty: &mut File, title: &str) {
+    let _ = write!(tty, "\x1b]0;{}\x07", title);
+    let _ = tty.flush();
+}
+
+/// Sleeps for `wait`, showing the time left in the terminal title with
+/// OSC 0 when `title` is set, so that it can be seen with the window in the
+/// background. The previous title is put back afterwards where the terminal
+/// keeps a title stack. Without a controlling terminal it only sleeps.
+pub fn wait(wait: Duration, title: bool) {
+    let tty = if title { std::fs::OpenOptions::new().write(true).open("/dev/tty").ok() } else { None };
+    let mut tty = match tty {
+        Some(tty) => tty,
+        None => return std::thread::sleep(wait),
+    };
+
+    let _ = tty.write_all(PUSH_TITLE.as_bytes());
+    let end = Instant::now() + wait;
+    loop {
+        let left = end.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        // Whole seconds, rounded up so that 0s is never shown
+        let shown = Duration::from_secs(left.as_secs() + u64::from(left.subsec_nanos() > 0));
+        set_title(&mut tty, &format!("passphrs: clearing in {}", humantime::format_duration(shown)));
+        std::thread::sleep(left.min(TICK));
+    }
+    let _ = tty.write_all(POP_TITLE.as_bytes());
+    let _ = tty.flush();
+}
@@ -0,0 +1,74 @@
+//! Deterministic passphrases. The master secret is stretched with Argon2id,
+//! then HKDF-SHA256 expands it with the label into the seed of a ChaCha20
+//! generator, which picks words exactly as a random one would. The same
+//! secret, label and settings always give the same passphrase, so nothing
+//! needs to be stored.
+
+use std::io::{BufRead, IsTerminal};
+
+use anyhow::{anyhow, bail, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use hkdf::Hkdf;
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use sha2::Sha256;
+
+/// Argon2id salt. Fixed, since a random one would have to be stored; the
+/// label keeps sites apart instead.
+const SALT: &[u8] = b"passphrs derive v1";
+/// Argon2id memory cost in KiB, costlier than keyfile's default since a
+/// master secret protects every derived passphrase
+const M_COST: u32 = 64 * 1024;
+const T_COST: u32 = 3;
+const P_COST: u32 = 1;
+
+/// Environment variable holding the master secret where there is no one to
+/// ask, as under Terraform
+pub const SECRET_VAR: &str = "PASSPHRS_MASTER_SECRET";
+
+/// The master secret from SECRET_VAR
+pub fn env_secret() -> Result<String> {
+    match std::env::var(SECRET_VAR) {
+        Ok(secret) if !secret.is_empty() => Ok(secret),
+        _ => bail!("Set {} to the master secret to derive passphrases from", SECRET_VAR),
+    }
+}
+
+/// Reads the master secret without echoing it, asking twice on a terminal
+/// since a typo would silently derive a different passphrase
+pub fn master_secret() -> Result<String> {
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        let secret = line.trim_end_matches(&['\r', '\n'][..]).to_string();
+        if secret.is_empty() {
+            bail!("No master secret given");
+        }
+        return Ok(secret);
+    }
+    let secret = rpassword::prompt_password("Master secret: ")?;
+    if secret.is_empty() {
+        bail!("No master secret given");
+    }
+    if rpassword::prompt_password("Repeat master secret: ")? != secret {
+        bail!("The master secrets do not match");
+    }
+    Ok(secret)
+}
+
+/// The generator that picks the passphrase for `label`. `bound`, such as a
+/// security key's hmac-secret answer, is mixed in as the HKDF salt, so the
+/// passphrase also needs whatever produced it.
+pub fn rng(secret: &str, label: &str, bound: Option<&[u8]>) -> Result<ChaCha20Rng> {
+    let params = Params::new(M_COST, T_COST, P_COST, Some(32)).map_err(|e| anyhow!("Invalid parameters: {}", e))?;
+    let mut stretched = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(secret.as_bytes(), SALT, &mut stretched)
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+
+    let mut seed = [0u8; 32];
+    Hkdf::<Sha256>::new(bound, &stretched)
+        .expand(label.as_bytes(), &mut seed)
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(ChaCha20Rng::from_seed(seed))
+}
@@ -0,0 +1,101 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::clipboard::{self, Clipboard};
+use crate::countdown;
+
+/// Name of the hidden subcommand the background process runs
+pub const WORKER: &str = "clipboard-worker";
+/// Line the worker prints once the clipboard is set
+const COPIED: &str = "copied";
+
+/// Everything the background process needs to copy the passphrase and
+/// later take it back out of the clipboard
+#[derive(Serialize, Deserialize)]
+pub struct Handoff {
+    pub contents: String,
+    pub previous: Option<String>,
+    pub wait: Duration,
+    pub osc52: bool,
+    pub clip_exe: bool,
+    /// Ring the terminal bell once the clipboard is released
+    pub bell: bool,
+    pub title_countdown: bool,
+}
+
+/// Starts a background copy of passphrs that sets the clipboard, holds it
+/// for the wait and clears it, so the shell gets its prompt back at once.
+/// The handoff goes over a pipe, never the command line, and this returns
+/// once the clipboard is set or setting it failed.
+pub fn spawn(handoff: &Handoff) -> Result<()> {
+    let mut command = Command::new(std::env::current_exe().context("Could not find the passphrs executable")?);
+    command.arg(WORKER)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        // A process group of its own keeps ^C and job control signals
+        // meant for the shell's foreground job away from it
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        command.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+    let mut child = command.spawn().context("Could not start the background process")?;
+
+    let mut stdin = child.stdin.take().unwrap();
+    serde_json::to_writer(&mut stdin, handoff)?;
+    stdin.write_all(b"\n")?;
+    drop(stdin);
+
+    let mut status = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut status)?;
+    match status.trim_end() {
+        COPIED => Ok(()),
+        "" => bail!("The background process exited without setting the clipboard"),
+        err => bail!("{}", err),
+    }
+}
+
+/// Runs in the background process: reads a handoff from stdin, reports on
+/// stdout whether the clipboard could be set, then waits and releases it
+pub fn worker() -> Result<()> {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    let handoff: Handoff = serde_json::from_str(&line).context("Malformed handoff")?;
+
+    let clipboard = if handoff.osc52 {
+        Clipboard::osc52()
+    } else if handoff.clip_exe {
+        Ok(Clipboard::ClipExe)
+    } else {
+        Clipboard::new()
+    };
+    let mut clipboard = match clipboard.and_then(|mut c| c.set_contents(handoff.contents.clone()).map(|()| c)) {
+        Ok(clipboard) => clipboard,
+        Err(err_) => {
+            println!("{:#}", err_);
+            return Ok(());
+        }
+    };
+    println!("{}", COPIED);
+
+    if !handoff.wait.is_zero() {
+        countdown::wait(handoff.wait, handoff.title_countdown);
+        clipboard.release(&handoff.contents, handoff.previous);
+        if handoff.bell {
+            clipboard::bell();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use anyhow::{bail, Context, Result};
+
+use passphrs::case::Case;
+
+/// A diceware-numbered word list: each line is a roll, such as 31456, then
+/// whitespace and the word it selects
+pub struct Numbered {
+    words: HashMap<String, String>,
+    /// Dice per roll
+    dice: usize,
+}
+
+impl Numbered {
+    /// Reads the list, making each word what `wordlist::parse` would with
+    /// the same `raw`, so dice pick the words the list is counted with
+    pub fn parse(text: &str, raw: bool) -> Result<Numbered> {
+        let mut words = HashMap::new();
+        let mut dice = 0;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let (roll, word) = match line.split_once(char::is_whitespace) {
+                Some((roll, word)) => (roll, word.trim()),
+                None if line.is_empty() => continue,
+                None => bail!("Line {} of the word list has no dice roll and word", number + 1),
+            };
+            let word = if raw {
+                word.to_string()
+            } else {
+                word.chars().filter(|c| c.is_alphabetic()).collect::<String>().to_ascii_lowercase()
+            };
+            if word.is_empty() {
+                bail!("Line {} of the word list has no word after its dice roll", number + 1);
+            }
+            if !valid_roll(roll) {
+                bail!("Line {} of the word list does not start with a dice roll", number + 1);
+            }
+            if dice == 0 {
+                dice = roll.len();
+            } else if roll.len() != dice {
+                bail!("Line {} of the word list has a {}-dice roll, others have {}", number + 1, roll.len(), dice);
+            }
+            words.insert(roll.to_string(), word);
+        }
+
+        if words.len() != 6usize.pow(dice as u32) || dice == 0 {
+            bail!("The word list has {} numbered words, but {} dice select from {}", words.len(), dice, 6usize.pow(dice as u32));
+        }
+        Ok(Numbered { words, dice })
+    }
+
+    pub fn dice(&self) -> usize {
+        self.dice
+    }
+
+    /// The roll that selects `word`
+    pub fn roll(&self, word: &str) -> Option<&str> {
+        self.words.iter().find(|(_, w)| w.eq_ignore_ascii_case(word)).map(|(roll, _)| roll.as_str())
+    }
+
+    /// The words `rolls` select, cased and joined
+    pub fn phrase(&self, rolls: &[String], case: &Case, separator: &str) -> Result<String> {
+        let mut words = Vec::new();
+        for (n, roll) in rolls.iter().enumerate() {
+            if roll.len() != self.dice || !valid_roll(roll) {
+                bail!("{} is not a roll of {} dice", roll, self.dice);
+            }
+            let mut word = self.words[roll].clone();
+            // Only fixed styles are allowed, so this draws nothing from the
+            // generator
+            case.for_position(n).apply(&mut word, &mut rand::thread_rng());
+            words.push(word);
+        }
+        Ok(words.join(separator))
+    }
+}
+
+fn valid_roll(roll: &str) -> bool {
+    !roll.is_empty() && roll.chars().all(|c| ('1'..='6').contains(&c))
+}
+
+/// Rolls separated by whitespace, ignoring anything after a #
+pub fn parse_rolls(text: &str) -> Result<Vec<String>> {
+    let rolls: Vec<String> = text.lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(str::split_whitespace)
+        .map(str::to_string)
+        .collect();
+    if rolls.is_empty() {
+        bail!("No dice rolls given");
+    }
+    Ok(rolls)
+}
+
+/// Reads rolls separated by whitespace from stdin, prompting on a terminal
+pub fn read_rolls(dice: usize, count: usize) -> Result<Vec<String>> {
+    eprintln!("Enter your {} rolls of {} dice each, separated by spaces:", count, dice);
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    parse_rolls(&line)
+}
+
+/// Reads rolls recorded in a file, e.g. during an offline ceremony, one or
+/// more to a line
+pub fn read_rolls_file(path: &str) -> Result<Vec<String>> {
+    parse_rolls(&std::fs::read_to_string(path).with_context(|| format!("Could not read {}", path))?)
+}
@@ -0,0 +1,85 @@
+use clap::ArgEnum;
+
+/// Alternative renderings of a passphrase for --info
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Display {
+    Plain,
+    /// 8-dot computer braille, one cell per character
+    Braille,
+    /// International Morse code, with " / " between words
+    Morse,
+}
+
+/// North American Braille ASCII: the cell with dot pattern `i` stands for the
+/// `i`th character, covering 0x20 to 0x5F
+const BRAILLE_ASCII: &str = " A1B'K2L@CIF/MSP\"E3H9O6R^DJG>NTQ,*5<-U8V.%[$+X!&;:4\\0Z7(_?W]#Y)=";
+const BRAILLE_BASE: u32 = 0x2800;
+/// Dot 7 marks capitals and the other characters from 0x40 to 0x5F
+const DOT_7: u32 = 0x40;
+/// All eight dots, shown for characters computer braille cannot represent
+const UNKNOWN_CELL: char = '\u{28ff}';
+
+/// Maps a character to its computer braille cell. Lowercase letters use the
+/// plain six-dot cell and uppercase letters add dot 7, so case survives.
+fn braille_cell(c: char) -> char {
+    let code = c as u32;
+    let (base, dot_7) = match code {
+        0x20..=0x3f => (c, 0),
+        0x40..=0x5f => (c, DOT_7),
+        0x60..=0x7e => (char::from_u32(code - 0x20).unwrap(), 0),
+        _ => return UNKNOWN_CELL,
+    };
+    let dots = BRAILLE_ASCII.find(base).unwrap() as u32;
+    char::from_u32(BRAILLE_BASE + dots + dot_7).unwrap()
+}
+
+/// ITU-R M.1677 Morse code plus the widely used non-ITU punctuation
+const MORSE: [(char, &str); 54] = [
+    ('a', ".-"), ('b', "-..."), ('c', "-.-."), ('d', "-.."), ('e', "."), ('f', "..-."),
+    ('g', "--."), ('h', "...."), ('i', ".."), ('j', ".---"), ('k', "-.-"), ('l', ".-.."),
+    ('m', "--"), ('n', "-."), ('o', "---"), ('p', ".--."), ('q', "--.-"), ('r', ".-."),
+    ('s', "..."), ('t', "-"), ('u', "..-"), ('v', "...-"), ('w', ".--"), ('x', "-..-"),
+    ('y', "-.--"), ('z', "--.."),
+    ('0', "-----"), ('1', ".----"), ('2', "..---"), ('3', "...--"), ('4', "....-"),
+    ('5', "....."), ('6', "-...."), ('7', "--..."), ('8', "---.."), ('9', "----."),
+    ('.', ".-.-.-"), (',', "--..--"), ('?', "..--.."), ('\'', ".----."), ('!', "-.-.--"),
+    ('/', "-..-."), ('(', "-.--."), (')', "-.--.-"), ('&', ".-..."), (':', "---..."),
+    (';', "-.-.-."), ('=', "-...-"), ('+', ".-.-."), ('-', "-....-"), ('_', "..--.-"),
+    ('"', ".-..-."), ('$', "...-..-"), ('@', ".--.-."),
+];
+
+/// Encodes a phrase in Morse. Letters are separated by spaces and words by
+/// " / ". Case cannot be sent, and unknown characters become `<?>`.
+fn morse(phrase: &str) -> String {
+    phrase.split(' ')
+        .map(|word| word.chars()
+            .map(|c| MORSE.iter()
+                .find(|(m, _)| *m == c.to_ascii_lowercase())
+                .map_or("<?>", |(_, code)| code))
+            .collect::<Vec<_>>()
+            .join(" "))
+        .collect::<Vec<_>>()
+        .join(" / ")
+}
+
+/// Renders `phrase` in the chosen form. `width` wraps braille into lines of
+/// that many cells, as embossers need.
+pub fn render(phrase: &str, display: Display, width: Option<usize>) -> Vec<String> {
+    match display {
+        Display::Plain => vec![phrase.to_string()],
+        Display::Braille => {
+            let cells: Vec<char> = phrase.chars().map(braille_cell).collect();
+            match width {
+                Some(width) if width > 0 => cells.chunks(width).map(|line| line.iter().collect()).collect(),
+                _ => vec![cells.into_iter().collect()],
+            }
+        }
+        Display::Morse => {
+            let mut lines = vec![morse(phrase)];
+            if phrase.chars().any(char::is_uppercase) {
+                lines.push("(Morse does not carry letter case)".to_string());
+            }
+            lines
+        }
+    }
+}
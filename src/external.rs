@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use clap::App;
+
+/// Reads a Terraform external data source query from stdin and turns it into
+/// command line arguments. `label` names what the passphrase is derived for;
+/// every other key names an option of `app` with `_` or `-` separating
+/// words, where "true" passes a bare flag and "false" omits it.
+pub fn query_args(app: &App) -> Result<Vec<String>> {
+    let mut query: BTreeMap<String, String> = serde_json::from_reader(std::io::stdin())
+        .context("The query must be a JSON object of strings")?;
+    let label = match query.remove("label") {
+        Some(label) if !label.is_empty() => label,
+        _ => bail!("The query needs a label naming what the passphrase is for"),
+    };
+
+    let mut args = vec![env!("CARGO_PKG_NAME").to_string()];
+    for (key, value) in query {
+        let name = key.replace('_', "-");
+        let long = app.get_arguments()
+            .find(|arg| arg.get_name() == name)
+            .and_then(|arg| arg.get_long())
+            .unwrap_or(&name);
+        let flag = format!("--{}", long);
+        match value.as_str() {
+            "true" => args.push(flag),
+            "false" => {}
+            _ => args.extend([flag, value]),
+        }
+    }
+    args.extend(["external-data".to_string(), label]);
+    Ok(args)
+}
+
+/// Writes the result object Terraform expects on stdout
+pub fn respond(phrase: &str) -> Result<()> {
+    let result = BTreeMap::from([("passphrase", phrase)]);
+    println!("{}", serde_json::to_string(&result)?);
+    Ok(())
+}
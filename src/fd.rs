@@ -0,0 +1,49 @@
+use anyhow::{bail, Result};
+
+/// Checks that `fd` is an open descriptor passphrs may write to, without
+/// writing to it
+#[cfg(unix)]
+pub fn check(fd: u32) -> Result<()> {
+    if fd <= 2 {
+        bail!("--fd {} is stdin, stdout or stderr; pass a descriptor opened for passphrs", fd);
+    }
+    // Too large a number wraps negative, which fcntl rejects as not open
+    let raw = fd as libc::c_int;
+    if unsafe { libc::fcntl(raw, libc::F_GETFD) } < 0 {
+        bail!("File descriptor {} is not open", fd);
+    }
+    let flags = unsafe { libc::fcntl(raw, libc::F_GETFL) };
+    if flags < 0 || flags & libc::O_ACCMODE == libc::O_RDONLY {
+        bail!("File descriptor {} is not open for writing", fd);
+    }
+    Ok(())
+}
+
+/// Writes `secret` as is, with no newline, to the inherited file descriptor
+/// `fd` and closes it, for a program that started passphrs with a pipe
+#[cfg(unix)]
+pub fn write(fd: u32, secret: &str) -> Result<()> {
+    use std::io::Write;
+
+    use anyhow::Context;
+
+    check(fd)?;
+    // Opening /dev/fd/N reaches the descriptor without taking ownership of a
+    // raw one that may not be open
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(format!("/dev/fd/{}", fd))
+        .with_context(|| format!("File descriptor {} is not open for writing", fd))?;
+    file.write_all(secret.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn check(_fd: u32) -> Result<()> {
+    bail!("--fd is only supported on Unix");
+}
+
+#[cfg(not(unix))]
+pub fn write(_fd: u32, _secret: &str) -> Result<()> {
+    bail!("--fd is only supported on Unix");
+}
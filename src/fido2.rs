@@ -0,0 +1,54 @@
+//! The FIDO2 hmac-secret extension, through libfido2's fido2-assert. A
+//! security key answers a salt with an HMAC keyed by a secret that never
+//! leaves it, so a passphrase derived with the answer cannot be recomputed
+//! without the key.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+/// Relying party the credential must have been made for
+pub const RP_ID: &str = "passphrs";
+/// Prefix of the hmac-secret salt, which the label completes
+const SALT_PREFIX: &[u8] = b"passphrs fido2 v1 ";
+
+/// The security key's hmac-secret answer for `label`, from the credential
+/// `credential` (base64, as fido2-cred printed it) on `device`, such as
+/// /dev/hidraw0. The key must be touched.
+pub fn hmac_secret(device: &str, credential: &str, label: &str) -> Result<Vec<u8>> {
+    let salt = Sha256::digest([SALT_PREFIX, label.as_bytes()].concat());
+    // Nothing checks the assertion's signature, so any client data will do
+    let client_data_hash = Sha256::digest(label.as_bytes());
+    let input = format!("{}\n{}\n{}\n{}\n", STANDARD.encode(client_data_hash), RP_ID, credential.trim(),
+        STANDARD.encode(salt));
+
+    let mut child = Command::new("fido2-assert")
+        .args(["-G", "-h", device])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Could not run fido2-assert, which comes with libfido2")?;
+    eprintln!("Touch your security key...");
+    child.stdin.take().context("Could not write to fido2-assert")?.write_all(input.as_bytes())?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        bail!("fido2-assert failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    // The hmac secret is the last line, after the authenticator data and
+    // signature
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let secret = match stdout.lines().last() {
+        Some(line) => STANDARD.decode(line.trim()).context("fido2-assert printed an invalid hmac secret")?,
+        None => bail!("fido2-assert printed no hmac secret"),
+    };
+    if secret.len() != 32 {
+        bail!("The security key answered with {} bytes instead of 32; was the credential made with -h?", secret.len());
+    }
+    Ok(secret)
+}
@@ -0,0 +1,59 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::open_private;
+
+const KEY_FILE: &str = "fingerprint.key";
+const STORE_FILE: &str = "fingerprints";
+const KEY_LEN: usize = 32;
+
+fn data_dir() -> Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| anyhow!("Could not find a data directory for fingerprints"))?
+        .join("passphrs");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Loads the local HMAC key, creating it on first use. The key never leaves
+/// this machine, so fingerprints cannot be brute forced from the store alone.
+fn key(dir: &Path) -> Result<Vec<u8>> {
+    let path = dir.join(KEY_FILE);
+    let mut key = vec![0; KEY_LEN];
+    match File::open(&path) {
+        Ok(mut file) => file.read_exact(&mut key)?,
+        Err(_) => {
+            rand::rngs::OsRng.fill_bytes(&mut key);
+            open_private(&path, OpenOptions::new().write(true).create_new(true))?.write_all(&key)?;
+        }
+    }
+    Ok(key)
+}
+
+/// Records the fingerprint of `secret`, returning true if it was already
+/// present. Only salted HMACs are stored, never anything recoverable.
+pub fn check_and_record(secret: &str) -> Result<bool> {
+    let dir = data_dir()?;
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key(&dir)?)?;
+    mac.update(secret.as_bytes());
+    let fingerprint: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+
+    let path = dir.join(STORE_FILE);
+    if let Ok(file) = File::open(&path) {
+        for line in BufReader::new(file).lines() {
+            if line? == fingerprint {
+                return Ok(true);
+            }
+        }
+    }
+
+    let mut file = open_private(&path, OpenOptions::new().append(true).create(true))?;
+    writeln!(file, "{}", fingerprint)?;
+    Ok(false)
+}
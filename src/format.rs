@@ -0,0 +1,186 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgEnum;
+
+/// Credential file formats the passphrase can be embedded in
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// A ~/.netrc machine entry
+    Netrc,
+    /// A ~/.pgpass line
+    Pgpass,
+    /// A PostgreSQL connection URI
+    Dsn,
+    /// A KEY="value" line for a .env file
+    Dotenv,
+    /// Store as a GitHub Actions secret with `gh secret set`, printing nothing
+    GithubSecret,
+    /// With --info, the whole report as JSON
+    Json,
+    /// An SVG badge of the settings' entropy, e.g. for a runbook, printing no passphrase
+    Badge,
+}
+
+/// Where the credential will be used
+pub struct Target<'a> {
+    pub host: Option<&'a str>,
+    pub login: Option<&'a str>,
+    pub port: Option<u16>,
+    pub database: Option<&'a str>,
+    /// Variable or secret name
+    pub key: Option<&'a str>,
+}
+
+/// Escapes the field separator and backslash as .pgpass requires
+fn pgpass_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace(':', "\\:")
+}
+
+/// Percent-encodes everything outside the URI unreserved set
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Escapes a value for a double quoted dotenv string
+fn dotenv_escape(s: &str) -> String {
+    s.chars()
+        .flat_map(|c| match c {
+            '\\' | '"' | '$' | '`' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect()
+}
+
+/// Checks that `gh` is authenticated and a key was given, without setting
+/// any secret
+pub fn check_github_secret(target: &Target) -> Result<()> {
+    if target.key.is_none() {
+        bail!("--format github-secret needs --key");
+    }
+    let status = Command::new("gh")
+        .args(["auth", "status"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("Could not run gh")?;
+    if !status.success() {
+        bail!("gh is not authenticated, run gh auth login");
+    }
+    Ok(())
+}
+
+/// Writes `secret` as the GitHub Actions secret `key` of the current
+/// repository, passing it to `gh` on stdin
+pub fn github_secret(secret: &str, target: &Target) -> Result<()> {
+    let key = match target.key {
+        Some(key) => key,
+        None => bail!("--format github-secret needs --key"),
+    };
+
+    let mut child = Command::new("gh")
+        .args(["secret", "set", key])
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("Could not run gh")?;
+    child.stdin.take().unwrap().write_all(secret.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("gh secret set failed ({})", status);
+    }
+    Ok(())
+}
+
+/// Renders `secret` as a line for a credential file. Formats with side
+/// effects, such as `GithubSecret`, are handled by their own functions.
+pub fn render(format: Format, secret: &str, target: &Target) -> Result<String> {
+    if let Format::Json = format {
+        bail!("--format json is only available with --info");
+    }
+    if let Format::Badge = format {
+        bail!("--format badge describes the settings and cannot embed a passphrase");
+    }
+    if let Format::Dotenv = format {
+        return match target.key {
+            Some(key) => Ok(format!("{}=\"{}\"", key, dotenv_escape(secret))),
+            None => bail!("--format dotenv needs --key"),
+        };
+    }
+
+    let host = match target.host {
+        Some(host) => host,
+        None => bail!("--format {:?} needs --host", format),
+    };
+
+    Ok(match format {
+        Format::Netrc => {
+            if secret.chars().any(char::is_whitespace) {
+                bail!("netrc passwords cannot contain whitespace; choose a different --separator");
+            }
+            match target.login {
+                Some(login) => format!("machine {} login {} password {}", host, login, secret),
+                None => bail!("--format netrc needs --login"),
+            }
+        }
+        Format::Pgpass => format!(
+            "{}:{}:{}:{}:{}",
+            pgpass_escape(host),
+            target.port.map_or("*".to_string(), |port| port.to_string()),
+            target.database.map_or("*".to_string(), pgpass_escape),
+            target.login.map_or("*".to_string(), pgpass_escape),
+            pgpass_escape(secret)),
+        Format::Dsn => {
+            let login = match target.login {
+                Some(login) => login,
+                None => bail!("--format dsn needs --login"),
+            };
+            let mut dsn = format!("postgresql://{}:{}@{}", percent_encode(login), percent_encode(secret), host);
+            if let Some(port) = target.port {
+                dsn += &format!(":{}", port);
+            }
+            if let Some(database) = target.database {
+                dsn += &format!("/{}", percent_encode(database));
+            }
+            dsn
+        }
+        Format::Dotenv | Format::GithubSecret | Format::Json | Format::Badge => unreachable!(),
+    })
+}
+
+/// Rough width in pixels of `text` in the badge's 11px Verdana
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+/// A flat shields.io-style SVG badge reading "entropy: N bits", colored as
+/// the --info strength bar is
+pub fn badge(entropy: f64) -> String {
+    let label = "entropy";
+    let message = format!("{:.0} bits", entropy.floor());
+    let color = match crate::color::strength(entropy) {
+        "weak" => "#e05d44",
+        "fair" => "#dfb317",
+        _ => "#4c1",
+    };
+    let (left, right) = (text_width(label), text_width(&message));
+    let width = left + right;
+    format!(
+        concat!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">"##,
+            r##"<title>{label}: {message}</title>"##,
+            r##"<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>"##,
+            r##"<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>"##,
+            r##"<g clip-path="url(#r)"><rect width="{left}" height="20" fill="#555"/><rect x="{left}" width="{right}" height="20" fill="{color}"/><rect width="{width}" height="20" fill="url(#s)"/></g>"##,
+            r##"<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">"##,
+            r##"<text x="{label_x}" y="14">{label}</text><text x="{message_x}" y="14">{message}</text></g></svg>"##,
+        ),
+        width = width, left = left, right = right, color = color, label = label, message = message,
+        label_x = left / 2, message_x = left + right / 2,
+    )
+}
@@ -0,0 +1,45 @@
+/// Uptime below which the kernel pool may not have gathered enough entropy,
+/// as in a freshly booted or cloned cloud image
+#[cfg(target_os = "linux")]
+const MIN_UPTIME_SECS: f64 = 60.0;
+/// Pool estimate below which older kernels are considered starved
+#[cfg(target_os = "linux")]
+const MIN_ENTROPY_AVAIL: u32 = 128;
+
+/// Looks for signs that the operating system's random number generator is not
+/// yet properly seeded, returning a description of each problem found
+#[cfg(target_os = "linux")]
+pub fn check() -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    // getrandom fails with EAGAIN in non-blocking mode until the pool has
+    // been initialized at least once
+    let mut buf = [0u8; 1];
+    let ret = unsafe { libc::getrandom(buf.as_mut_ptr().cast(), buf.len(), libc::GRND_NONBLOCK) };
+    if ret < 0 && std::io::Error::last_os_error().raw_os_error() == Some(libc::EAGAIN) {
+        warnings.push("the kernel random pool is not initialized yet".to_string());
+    }
+
+    let read_number = |path: &str| -> Option<f64> {
+        std::fs::read_to_string(path).ok()?.split_whitespace().next()?.parse().ok()
+    };
+    if let Some(avail) = read_number("/proc/sys/kernel/random/entropy_avail") {
+        if (avail as u32) < MIN_ENTROPY_AVAIL {
+            warnings.push(format!("the kernel entropy estimate is only {} bits", avail));
+        }
+    }
+    if let Some(uptime) = read_number("/proc/uptime") {
+        if uptime < MIN_UPTIME_SECS {
+            warnings.push(format!(
+                "the system booted {:.0} seconds ago; freshly started or cloned machines may share random state",
+                uptime));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn check() -> Vec<String> {
+    Vec::new()
+}
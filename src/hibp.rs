@@ -0,0 +1,35 @@
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Context, Result};
+use sha1::{Digest, Sha1};
+
+const RANGE_API: &str = "https://api.pwnedpasswords.com/range/";
+/// Generous for one small response, short enough not to stall an offline run
+const TIMEOUT_SECS: &str = "5";
+
+/// Whether Have I Been Pwned has seen `phrase` in a breach. Only the first
+/// five hex digits of its SHA-1 are sent, and the matching is done here
+/// among every hash that shares them. Needs curl, and fails if the API
+/// cannot be reached.
+pub fn pwned(phrase: &str) -> Result<bool> {
+    let hash: String = Sha1::digest(phrase.as_bytes()).iter().map(|b| format!("{:02X}", b)).collect();
+    let (prefix, suffix) = hash.split_at(5);
+
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", TIMEOUT_SECS])
+        // Padding hides from an eavesdropper how many hashes share the prefix
+        .args(["--header", "Add-Padding: true"])
+        .arg(format!("{}{}", RANGE_API, prefix))
+        .stdin(Stdio::null())
+        .output()
+        .context("Could not run curl")?;
+    if !output.status.success() {
+        bail!("Could not reach Have I Been Pwned: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    // Lines are SUFFIX:COUNT, with padding entries counted 0
+    Ok(String::from_utf8_lossy(&output.stdout).lines().any(|line| match line.trim().split_once(':') {
+        Some((candidate, count)) => candidate.eq_ignore_ascii_case(suffix) && count.trim() != "0",
+        None => false,
+    }))
+}
@@ -0,0 +1,315 @@
+//! Known-answer tests for the generation path. Each vector fixes a seed and
+//! a set of options and records the passphrase upstream produces, so a
+//! packaged build can confirm it selects, cases and salts exactly as
+//! upstream does. Vectors use ChaCha20 seeded with `seed_from_u64` and a
+//! built-in word list, so they depend on neither the OS generator nor the
+//! installed list. `cargo test` runs them too.
+
+#[cfg(feature = "kat")]
+use anyhow::{bail, Result};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+use passphrs::case::{parse_mask, Case, Language, Style};
+use passphrs::constraint::{DIGITS, SYMBOLS};
+use passphrs::pattern::{self, Alphabets};
+use passphrs::transform::{self, RandomSeparators, Substitute, Transform};
+use passphrs::{
+    build_balanced_with, build_passphrase, build_slots_with, build_unique_with, build_with, join_parts,
+    standard_transforms, wordlist::{self, List}, LetterWindow,
+};
+
+use crate::{bip39, derive};
+
+/// The first 32 words of the EFF large list
+const WORDS: [&str; 32] = [
+    "abacus", "abdomen", "abdominal", "abide", "abiding", "ability", "ablaze", "able",
+    "abnormal", "abrasion", "abrasive", "abreast", "abridge", "abroad", "abruptly", "absence",
+    "absentee", "absently", "absinthe", "absolute", "absolve", "abstain", "abstract", "absurd",
+    "accent", "acclaim", "acclimate", "accompany", "account", "accuracy", "accurate", "accustom",
+];
+
+struct Vector {
+    name: &'static str,
+    seed: u64,
+    length: usize,
+    separator: &'static str,
+    salt_length: usize,
+    salt_chars: &'static str,
+    case: Case,
+    expected: &'static str,
+}
+
+fn vectors() -> Vec<Vector> {
+    vec![
+        Vector {
+            name: "defaults",
+            seed: 0,
+            length: 7,
+            separator: " ",
+            salt_length: 1,
+            salt_chars: "0123456789",
+            case: Case::Style(Style::None, Language::Unicode),
+            expected: "accustom abnormal abnormal absinthe absurd abrasive abdomen5",
+        },
+        Vector {
+            name: "capitalized, joined",
+            seed: 1,
+            length: 5,
+            separator: "",
+            salt_length: 2,
+            salt_chars: "0123456789",
+            case: Case::Style(Style::Capitalized, Language::Unicode),
+            expected: "AbsentlyAbridgeAbsolveAbruptlyAbsinthe22",
+        },
+        Vector {
+            name: "uppercase, symbol salt",
+            seed: 2,
+            length: 4,
+            separator: "-",
+            salt_length: 3,
+            salt_chars: "!#$%&*+=?@",
+            case: Case::Style(Style::Upper, Language::Unicode),
+            expected: "ABDOMINAL-ABRASIVE-ABSINTHE-ACCLIMATE#==",
+        },
+        Vector {
+            name: "random case mask",
+            seed: 3,
+            length: 6,
+            separator: ".",
+            salt_length: 1,
+            salt_chars: "0123456789",
+            case: Case::Mask(parse_mask("?").unwrap(), 0.5, Language::Unicode),
+            expected: "ACcEnT4.aCcEnt.aCcEnt.acClaiM.ABseNtLY.ABdOMinAL",
+        },
+        Vector {
+            name: "no salt",
+            seed: 4,
+            length: 6,
+            separator: " ",
+            salt_length: 0,
+            salt_chars: "0123456789",
+            case: Case::Style(Style::Lower, Language::Unicode),
+            expected: "absinthe abridge absolute absolve abacus absently",
+        },
+        Vector {
+            name: "salt only",
+            seed: 5,
+            length: 0,
+            separator: " ",
+            salt_length: 16,
+            salt_chars: "abcdefghijklmnopqrstuvwxyz0123456789",
+            case: Case::Style(Style::None, Language::Unicode),
+            expected: "307x1ra4ciipqrjq",
+        },
+    ]
+}
+
+/// A vector for one generation mode, assembled as main assembles it
+struct Mode {
+    name: &'static str,
+    seed: u64,
+    generate: fn(&mut ChaCha20Rng, &[String]) -> String,
+    expected: &'static str,
+}
+
+fn capitalized() -> Case {
+    Case::Style(Style::Capitalized, Language::Unicode)
+}
+
+fn modes() -> Vec<Mode> {
+    vec![
+        Mode {
+            name: "unique",
+            seed: 10,
+            generate: |rng, list| {
+                join_parts(&build_unique_with(rng, list, 6, " ", &standard_transforms(&capitalized(), 1, DIGITS)))
+            },
+            expected: "Abdomen6 Accuracy Abnormal Absinthe Accurate Accompany",
+        },
+        Mode {
+            name: "pronounceable",
+            seed: 11,
+            generate: |rng, _| {
+                let case = Case::Style(Style::Lower, Language::Unicode);
+                join_parts(&build_passphrase(rng, &wordlist::syllables(), 5, "", 1, DIGITS, &case))
+            },
+            expected: "hozkog7wivretdo",
+        },
+        Mode {
+            name: "pattern",
+            seed: 12,
+            generate: |rng, list| {
+                let alphabets = Alphabets {
+                    digits: DIGITS.to_string(),
+                    symbols: SYMBOLS.to_string(),
+                    salt: DIGITS.to_string(),
+                };
+                let pattern = pattern::parse("Wd-Ww-s##").unwrap();
+                join_parts(&pattern.build(rng, list, &capitalized(), Language::Unicode, &alphabets))
+            },
+            expected: "Absentee3-Accurateabsence-+18",
+        },
+        Mode {
+            name: "template",
+            seed: 13,
+            generate: |rng, list| {
+                let slots = vec![list[..16].to_vec(), list[16..].to_vec(), list[..16].to_vec()];
+                join_parts(&build_slots_with(rng, &slots, "-", &standard_transforms(&capitalized(), 1, DIGITS)))
+            },
+            expected: "Abide-Absolve0-Abroad",
+        },
+        Mode {
+            name: "balance-length",
+            seed: 14,
+            generate: |rng, list| {
+                let window = LetterWindow { min: 30, max: 32 };
+                let transforms = standard_transforms(&capitalized(), 1, DIGITS);
+                join_parts(&build_balanced_with(rng, list, 4, " ", window, &transforms))
+            },
+            expected: "Abrasion Absolve4 Abnormal Account",
+        },
+        Mode {
+            name: "separator-set",
+            seed: 15,
+            generate: |rng, list| {
+                let separators = RandomSeparators { chars: "-_.,".to_string() };
+                let mut transforms: Vec<Box<dyn Transform>> = vec![Box::new(separators)];
+                transforms.extend(standard_transforms(&capitalized(), 1, DIGITS));
+                join_parts(&build_with(rng, list, 6, "-", &transforms))
+            },
+            expected: "Absolve.Absolute_Accompany_Accurate1-Accuracy_Acclimate",
+        },
+        Mode {
+            name: "substitute",
+            seed: 16,
+            generate: |rng, list| {
+                let mut transforms = standard_transforms(&capitalized(), 1, DIGITS);
+                let map = transform::parse_substitutions(transform::DEFAULT_SUBSTITUTIONS).unwrap();
+                transforms.push(Box::new(Substitute { map, prob: 0.5 }));
+                join_parts(&build_with(rng, list, 5, " ", &transforms))
+            },
+            expected: "Absently @bre@$t @brup7ly @cclim@te9 @bsurd",
+        },
+    ]
+}
+
+/// BIP39 mnemonics of fixed entropy, from the reference test vectors
+const BIP39: [(&[u8], &str); 4] = [
+    (&[0x00; 16], "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"),
+    (&[0x7f; 16], "legal winner thank year wave sausage worth useful legal winner thank yellow"),
+    (&[0x80; 16], "letter advice cage absurd amount doctor acoustic avoid letter advice cage above"),
+    (&[0xff; 32], "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo vote"),
+];
+
+/// Passphrases `derive` gives from the EFF large list for a secret and label,
+/// as (secret, label, length, separator, case, expected), with one digit of
+/// salt. Users rebuild these instead of storing them, so any change that
+/// alters one loses every passphrase derived before it.
+const DERIVED: [(&str, &str, usize, &str, Style, &str); 2] = [
+    ("correct horse battery staple", "example.com", 7, " ", Style::Capitalized,
+        "Pursuable Hypocrite Rubber Handwoven Mumps Boogieman Sensation8"),
+    ("correct horse battery staple", "bank", 4, "-", Style::Lower, "presoak-jaunt-transport2-caloric"),
+];
+
+/// Words cased by each language's rules, as (name, language, style, word,
+/// expected)
+const CASING: [(&str, Language, Style, &str, &str); 10] = [
+    ("unicode capitalized", Language::Unicode, Style::Capitalized, "\u{e9}t\u{e9}", "\u{c9}t\u{e9}"),
+    ("unicode capitalized multi-byte", Language::Unicode, Style::Capitalized, "\u{f1}and\u{fa}", "\u{d1}and\u{fa}"),
+    ("unicode capitalized decomposed", Language::Unicode, Style::Capitalized, "e\u{301}te\u{301}", "E\u{301}te\u{301}"),
+    ("unicode upper sharp s", Language::Unicode, Style::Upper, "stra\u{df}e", "STRASSE"),
+    ("unicode upper i", Language::Unicode, Style::Upper, "istanbul", "ISTANBUL"),
+    ("turkish capitalized i", Language::Turkish, Style::Capitalized, "istanbul", "\u{130}stanbul"),
+    ("turkish upper", Language::Turkish, Style::Upper, "\u{131}\u{15f}\u{131}k", "I\u{15e}IK"),
+    ("turkish lower", Language::Turkish, Style::Lower, "I\u{130}", "\u{131}i"),
+    ("dutch capitalized ij", Language::Dutch, Style::Capitalized, "ijsland", "IJsland"),
+    ("dutch capitalized i", Language::Dutch, Style::Capitalized, "insect", "Insect"),
+];
+
+/// What one vector produced against what upstream does
+struct Outcome {
+    name: String,
+    expected: String,
+    got: String,
+}
+
+/// Runs every vector
+fn outcomes() -> Vec<Outcome> {
+    let list: Vec<String> = WORDS.iter().map(|w| w.to_string()).collect();
+    let mut outcomes = Vec::new();
+    for vector in vectors() {
+        let mut rng = ChaCha20Rng::seed_from_u64(vector.seed);
+        let got = join_parts(&build_passphrase(
+            &mut rng,
+            &list,
+            vector.length,
+            vector.separator,
+            vector.salt_length,
+            vector.salt_chars,
+            &vector.case));
+        outcomes.push(Outcome { name: vector.name.to_string(), expected: vector.expected.to_string(), got });
+    }
+
+    for mode in modes() {
+        let got = (mode.generate)(&mut ChaCha20Rng::seed_from_u64(mode.seed), &list);
+        outcomes.push(Outcome { name: format!("mode: {}", mode.name), expected: mode.expected.to_string(), got });
+    }
+
+    let eff_large = wordlist::parse(List::EffLarge.text(), false, None, None);
+    for (secret, label, length, separator, style, expected) in DERIVED {
+        let transforms = standard_transforms(&Case::Style(style, Language::Unicode), 1, DIGITS);
+        let got = match derive::rng(secret, label, None) {
+            Ok(mut rng) => join_parts(&build_with(&mut rng, &eff_large, length, separator, &transforms)),
+            Err(err) => format!("error: {}", err),
+        };
+        outcomes.push(Outcome { name: format!("derive: {}", label), expected: expected.to_string(), got });
+    }
+
+    for (entropy, expected) in BIP39 {
+        outcomes.push(Outcome {
+            name: format!("bip39: {} words", expected.split(' ').count()),
+            expected: expected.to_string(),
+            got: bip39::from_entropy(entropy),
+        });
+    }
+
+    for (name, language, style, word, expected) in CASING {
+        let mut got = word.to_string();
+        Case::Style(style, language).apply(&mut got, &mut ChaCha20Rng::seed_from_u64(0));
+        outcomes.push(Outcome { name: format!("casing: {}", name), expected: expected.to_string(), got });
+    }
+    outcomes
+}
+
+/// Runs every vector, printing one line per vector, and fails if any output
+/// differs from upstream's
+#[cfg(feature = "kat")]
+pub fn kat_command() -> Result<()> {
+    let mut failures = 0;
+    for outcome in outcomes() {
+        if outcome.got == outcome.expected {
+            println!("ok    {}", outcome.name);
+        } else {
+            println!("FAIL  {}: expected {:?}, got {:?}", outcome.name, outcome.expected, outcome.got);
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        bail!("{} known-answer test(s) failed; this build does not generate like upstream", failures);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn vectors_match_upstream() {
+        let failures: Vec<String> = super::outcomes().into_iter()
+            .filter(|outcome| outcome.got != outcome.expected)
+            .map(|outcome| format!("{}: expected {:?}, got {:?}", outcome.name, outcome.expected, outcome.got))
+            .collect();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
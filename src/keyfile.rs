@@ -0,0 +1,78 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use anyhow::{anyhow, bail, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use clap::ArgEnum;
+use rand::RngCore;
+
+use crate::open_private;
+
+const SALT_LEN: usize = 16;
+
+/// Key derivation functions for turning a passphrase into key bytes
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Kdf {
+    Argon2id,
+}
+
+/// Where the salt and parameters of the key in `out` are written
+pub fn params_path(out: &str) -> String {
+    format!("{}.params", out)
+}
+
+/// Checks that a key and its parameters could be written to `out` without
+/// writing them
+pub fn check(out: &str) -> Result<()> {
+    let path = std::path::Path::new(out);
+    for existing in [out.to_string(), params_path(out)] {
+        if std::path::Path::new(&existing).exists() {
+            bail!("{} already exists", existing);
+        }
+    }
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => std::path::Path::new("."),
+    };
+    if !dir.is_dir() {
+        bail!("{} is not a directory", dir.display());
+    }
+    if std::fs::metadata(dir)?.permissions().readonly() {
+        bail!("{} is not writable", dir.display());
+    }
+    Ok(())
+}
+
+/// Derives `bytes` bytes of key from `phrase` with a fresh random salt and
+/// writes them to `out`. The salt and parameters are written next to it, in
+/// `params_path(out)`, so the key can be derived again from the memorized
+/// passphrase.
+pub fn write(kdf: Kdf, phrase: &str, bytes: usize, out: &str) -> Result<()> {
+    let mut salt = [0u8; SALT_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+
+    let mut key = vec![0u8; bytes];
+    let params = match kdf {
+        Kdf::Argon2id => {
+            let params = Params::new(
+                Params::DEFAULT_M_COST, Params::DEFAULT_T_COST, Params::DEFAULT_P_COST, Some(bytes))
+                .map_err(|e| anyhow!("Invalid key length: {}", e))?;
+            Argon2::new(Algorithm::Argon2id, Version::V0x13, params.clone())
+                .hash_password_into(phrase.as_bytes(), &salt, &mut key)
+                .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+            params
+        }
+    };
+
+    let salt_hex: String = salt.iter().map(|b| format!("{:02x}", b)).collect();
+    let description = format!("argon2id m={} t={} p={} bytes={} salt={}",
+        params.m_cost(), params.t_cost(), params.p_cost(), bytes, salt_hex);
+    let params_out = params_path(out);
+    open_private(params_out.as_ref(), OpenOptions::new().write(true).create_new(true))?
+        .write_all(format!("{}\n", description).as_bytes())?;
+    open_private(out.as_ref(), OpenOptions::new().write(true).create_new(true))?.write_all(&key)?;
+
+    eprintln!("Wrote {} key bytes to {} using {}. The salt is also in {}; keep it to derive the key again.",
+        bytes, out, description, params_out);
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+
+use passphrs::{join_parts, Part};
+
+/// Numbered device names to pair with credentials
+pub struct Batch<'a> {
+    pub count: usize,
+    pub prefix: &'a str,
+    pub start: usize,
+    pub entropy: f64,
+}
+
+/// Prints a unique passphrase for each device, as a CSV of label and
+/// passphrase or as a sheet of boxed labels to print and cut out
+pub fn batch(generate: &dyn Fn() -> Result<Vec<Part>>, batch: &Batch, csv: bool) -> Result<()> {
+    if batch.entropy.exp2() < batch.count as f64 {
+        bail!("These settings cannot produce {} distinct passphrases", batch.count);
+    }
+
+    let width = (batch.start + batch.count - 1).to_string().len().max(3);
+    let mut seen = HashSet::new();
+    let mut pairs = Vec::new();
+    while pairs.len() < batch.count {
+        let phrase = join_parts(&generate()?);
+        if seen.insert(phrase.clone()) {
+            let label = format!("{}{:0width$}", batch.prefix, batch.start + pairs.len(), width = width);
+            pairs.push((label, phrase));
+        }
+    }
+
+    if csv {
+        let mut writer = csv::Writer::from_writer(std::io::stdout());
+        writer.write_record(["label", "passphrase"])?;
+        for (label, phrase) in &pairs {
+            writer.write_record([label, phrase])?;
+        }
+        writer.flush()?;
+        return Ok(());
+    }
+
+    let inner = pairs.iter()
+        .map(|(label, phrase)| label.chars().count().max(phrase.chars().count() + "Password: ".len()))
+        .max()
+        .unwrap_or(0);
+    let rule = format!("+{}+", "-".repeat(inner + 2));
+    for (label, phrase) in &pairs {
+        println!("{}", rule);
+        println!("| {:<inner$} |", label, inner = inner);
+        println!("| {:<inner$} |", format!("Password: {}", phrase), inner = inner);
+        println!("{}", rule);
+        println!();
+    }
+    Ok(())
+}
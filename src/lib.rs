@@ -0,0 +1,305 @@
+//! Diceware-style passphrase generation. The `passphrs` command line tool is
+//! a wrapper around this crate.
+//!
+//! ```no_run
+//! use passphrs::PassphraseBuilder;
+//!
+//! let generated = PassphraseBuilder::default().length(6).separator("-").build().unwrap();
+//! println!("{} ({:.1} bits)", generated.secret, generated.entropy_bits);
+//! ```
+
+use std::ops::Deref;
+
+use rand::Rng;
+
+pub mod builder;
+pub mod case;
+pub mod constraint;
+pub mod pattern;
+pub mod strength;
+pub mod transform;
+pub mod wordlist;
+
+pub use builder::{BuildError, GeneratedPassphrase, Params, PassphraseBuilder, Scheme};
+use case::Case;
+use transform::{CaseWords, InjectSalt, Transform};
+
+/// A piece of a generated passphrase, kept apart so that output can treat
+/// words, separators and salt differently
+#[derive(Clone, Debug)]
+pub enum Part {
+    /// A cased word and its index in the word list
+    Word(String, usize),
+    Separator(String),
+    Salt(String),
+    Literal(String),
+}
+
+impl Part {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Part::Word(s, _) | Part::Separator(s) | Part::Salt(s) | Part::Literal(s) => s,
+        }
+    }
+}
+
+pub fn join_parts(parts: &[Part]) -> String {
+    parts.iter().map(Part::as_str).collect()
+}
+
+/// Picks `length` words from `list` at random, separated by `separator`
+pub fn select_words<R: Rng>(rng: &mut R, list: &[String], length: usize, separator: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    for i in 0..length {
+        let index = rng.gen_range(0..list.len());
+        if i != 0 {
+            parts.push(Part::Separator(separator.to_string()));
+        }
+        parts.push(Part::Word(list[index].clone(), index));
+    }
+    parts
+}
+
+/// Picks `length` different words from `list` at random, separated by
+/// `separator`. The list must have at least `length` words.
+pub fn select_unique_words<R: Rng>(rng: &mut R, list: &[String], length: usize, separator: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    for (i, index) in rand::seq::index::sample(rng, list.len(), length).into_iter().enumerate() {
+        if i != 0 {
+            parts.push(Part::Separator(separator.to_string()));
+        }
+        parts.push(Part::Word(list[index].clone(), index));
+    }
+    parts
+}
+
+/// Picks one word from each of `slots` in turn, separated by `separator`,
+/// for passphrases that follow a template such as adjective, noun, verb.
+/// Word indices are into the slot's own list.
+pub fn select_slot_words<R: Rng>(rng: &mut R, slots: &[Vec<String>], separator: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    for (i, list) in slots.iter().enumerate() {
+        let index = rng.gen_range(0..list.len());
+        if i != 0 {
+            parts.push(Part::Separator(separator.to_string()));
+        }
+        parts.push(Part::Word(list[index].clone(), index));
+    }
+    parts
+}
+
+/// Bits of the words of a passphrase following a template, as opposed to
+/// the `slots.len()` times log2 of a single list's length that `entropy`
+/// assumes
+pub fn slot_bits(slots: &[Vec<String>]) -> f64 {
+    slots.iter().map(|list| (list.len() as f64).log2()).sum()
+}
+
+/// Bounds on the total number of characters in a passphrase's words
+#[derive(Clone, Copy, Debug)]
+pub struct LetterWindow {
+    pub min: usize,
+    pub max: usize,
+}
+
+/// `shares[j][t]`: the share of sequences of `j` words from `list` whose
+/// lengths add up to `t` characters, for `j` up to `length`
+fn length_shares(list: &[String], length: usize) -> Vec<Vec<f64>> {
+    let lengths: Vec<usize> = list.iter().map(|w| w.chars().count()).collect();
+    let longest = lengths.iter().copied().max().unwrap_or(0);
+    let mut single = vec![0.0; longest + 1];
+    for &len in &lengths {
+        single[len] += 1.0 / list.len() as f64;
+    }
+
+    let mut shares = vec![vec![1.0]];
+    for j in 1..=length {
+        let previous = &shares[j - 1];
+        let mut next = vec![0.0; previous.len() + longest];
+        for (t, &p) in previous.iter().enumerate() {
+            for (len, &q) in single.iter().enumerate() {
+                next[t + len] += p * q;
+            }
+        }
+        shares.push(next);
+    }
+    shares
+}
+
+/// Share of `shares` from `min` to `max` characters
+fn share_between(shares: &[f64], min: isize, max: isize) -> f64 {
+    let min = min.max(0) as usize;
+    if max < 0 || min >= shares.len() {
+        return 0.0;
+    }
+    shares[min..=(max as usize).min(shares.len() - 1)].iter().sum()
+}
+
+/// Probability that `length` words picked uniformly from `list` fit in
+/// `window`. Picking only from those that fit leaves `log2` of this many
+/// fewer bits.
+pub fn balance_acceptance(list: &[String], length: usize, window: LetterWindow) -> f64 {
+    let shares = length_shares(list, length);
+    share_between(&shares[length], window.min as isize, window.max as isize)
+}
+
+/// Picks `length` words from `list` whose lengths add up to a total within
+/// `window`, uniformly among all sequences that do. Each word's length is
+/// drawn in proportion to how many ways the remaining words can still fit,
+/// then the word uniformly among those of that length. The window must be
+/// reachable.
+pub fn select_balanced_words<R: Rng>(rng: &mut R, list: &[String], length: usize, separator: &str,
+    window: LetterWindow) -> Vec<Part> {
+    let shares = length_shares(list, length);
+    let mut by_length: Vec<Vec<usize>> = Vec::new();
+    for (index, word) in list.iter().enumerate() {
+        let len = word.chars().count();
+        if by_length.len() <= len {
+            by_length.resize(len + 1, Vec::new());
+        }
+        by_length[len].push(index);
+    }
+
+    let mut parts = Vec::new();
+    let mut used = 0;
+    for i in 0..length {
+        let rest = &shares[length - i - 1];
+        let weights: Vec<f64> = by_length.iter().enumerate()
+            .map(|(len, words)| {
+                let (min, max) = (window.min as isize - (used + len) as isize, window.max as isize - (used + len) as isize);
+                words.len() as f64 * share_between(rest, min, max)
+            })
+            .collect();
+        let mut pick = rng.gen_range(0.0..weights.iter().sum::<f64>());
+        let len = weights.iter().position(|&w| {
+            pick -= w;
+            pick < 0.0
+        }).unwrap_or_else(|| weights.iter().rposition(|&w| w > 0.0).unwrap_or(0));
+        let index = by_length[len][rng.gen_range(0..by_length[len].len())];
+        used += len;
+
+        if i != 0 {
+            parts.push(Part::Separator(separator.to_string()));
+        }
+        parts.push(Part::Word(list[index].clone(), index));
+    }
+    parts
+}
+
+/// Bits of choosing each separator between `phrase_len` words from
+/// `separator_choices`
+pub fn separator_bits(phrase_len: usize, separator_choices: usize) -> f64 {
+    phrase_len.saturating_sub(1) as f64 * (separator_choices.max(1) as f64).log2()
+}
+
+/// Bits lost by never repeating a word: choosing `length` different words
+/// from `list_len` leaves a falling factorial of passphrases rather than
+/// `list_len` to the power `length`. Negative, or 0 if words may repeat.
+pub fn unique_bits(list_len: usize, length: usize) -> f64 {
+    let n = list_len as f64;
+    (0..length).map(|i| ((n - i as f64) / n).log2()).sum()
+}
+
+/// Generates one passphrase of `length` words from `list`, then runs each
+/// of `transforms` over it in order
+pub fn build_with<R: Rng>(
+    rng: &mut R,
+    list: &[String],
+    length: usize,
+    separator: &str,
+    transforms: &[impl Deref<Target = dyn Transform>],
+) -> Vec<Part> {
+    let parts = select_words(rng, list, length, separator);
+    apply_transforms(rng, parts, transforms)
+}
+
+/// As `build_with`, but with no word used twice
+pub fn build_unique_with<R: Rng>(
+    rng: &mut R,
+    list: &[String],
+    length: usize,
+    separator: &str,
+    transforms: &[impl Deref<Target = dyn Transform>],
+) -> Vec<Part> {
+    let parts = select_unique_words(rng, list, length, separator);
+    apply_transforms(rng, parts, transforms)
+}
+
+/// As `build_with`, with each word drawn from its own slot's list
+pub fn build_slots_with<R: Rng>(
+    rng: &mut R,
+    slots: &[Vec<String>],
+    separator: &str,
+    transforms: &[impl Deref<Target = dyn Transform>],
+) -> Vec<Part> {
+    let parts = select_slot_words(rng, slots, separator);
+    apply_transforms(rng, parts, transforms)
+}
+
+/// As `build_with`, with the words' total length kept within `window`
+pub fn build_balanced_with<R: Rng>(
+    rng: &mut R,
+    list: &[String],
+    length: usize,
+    separator: &str,
+    window: LetterWindow,
+    transforms: &[impl Deref<Target = dyn Transform>],
+) -> Vec<Part> {
+    let parts = select_balanced_words(rng, list, length, separator, window);
+    apply_transforms(rng, parts, transforms)
+}
+
+fn apply_transforms<R: Rng>(rng: &mut R, mut parts: Vec<Part>, transforms: &[impl Deref<Target = dyn Transform>])
+    -> Vec<Part> {
+    for transform in transforms {
+        transform.apply(&mut parts, rng);
+    }
+    parts
+}
+
+/// The standard pipeline: case every word, then add salt after a random one.
+/// It is what [`PassphraseBuilder::standard`] adds for the builder's own case
+/// and salt settings.
+pub fn standard_transforms(case: &Case, salt_length: usize, salt_chars: &str) -> Vec<Box<dyn Transform>> {
+    vec![
+        Box::new(CaseWords(case.clone())),
+        Box::new(InjectSalt { length: salt_length, chars: salt_chars.to_string() }),
+    ]
+}
+
+/// Generates one passphrase of `length` words from `list`, with
+/// `salt_length` characters of salt after a random word
+pub fn build_passphrase<R: Rng>(
+    rng: &mut R,
+    list: &[String], 
+    length: usize, 
+    separator: &str, 
+    salt_length: usize,
+    salt_chars: &str,
+    case: &Case,
+) -> Vec<Part> {
+    build_with(rng, list, length, separator, &standard_transforms(case, salt_length, salt_chars))
+}
+
+/// Bits of entropy of the scheme, plus `adjust_bits`, and the length of a
+/// random ASCII password that would be as strong. Each gap between words
+/// has one of `separator_choices` separators, 1 for a fixed separator.
+pub fn entropy(
+    list_len: usize,
+    phrase_len: usize,
+    separator_choices: usize,
+    salt_len: usize,
+    salt_chars: &str,
+    adjust_bits: f64,
+) -> (f64, f64) {
+    // Summed as logarithms, since the number of combinations overflows an
+    // f64 for long passphrases
+    let mut bits = phrase_len as f64 * (list_len as f64).log2();
+    bits += separator_bits(phrase_len, separator_choices);
+    if salt_len > 0 {
+        bits += (phrase_len.max(1) as f64).log2() + salt_len as f64 * (salt_chars.chars().count() as f64).log2();
+    }
+
+    let entropy = bits + adjust_bits;
+    (entropy, entropy / 7.0)
+}
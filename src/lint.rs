@@ -0,0 +1,128 @@
+use std::io::{BufRead, IsTerminal};
+
+use anyhow::{bail, Context, Result};
+
+use passphrs::strength::{Assessment, Checker, Lockout, Terms, ATTACKS};
+use passphrs::wordlist;
+
+use crate::units::{Locale, Units};
+
+/// Terms in a --dictionary file: each line but blanks and # comments, and for
+/// an email address also the name and domain it is made of
+fn read_terms(path: &str) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Could not read {}", path))?;
+    let mut terms = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        if let Some((name, domain)) = line.split_once('@') {
+            terms.push(name.to_string());
+            // Every label of the domain but the top level one
+            terms.extend(domain.split('.').rev().skip(1).map(str::to_string));
+        }
+        terms.push(line.to_string());
+    }
+    Ok(terms)
+}
+
+/// A checker with the bundled word lists as its dictionary, the config
+/// file's `terms` and the terms in each of `dictionaries`
+fn checker(terms: &[String], dictionaries: &[String]) -> Result<Checker> {
+    let dictionary = wordlist::BUNDLED.iter()
+        .flat_map(|list| wordlist::parse(list.text(), false, None, None))
+        .collect();
+    let mut checker = Checker::new(dictionary);
+    if !terms.is_empty() {
+        checker = checker.with(Terms::new("the terms in the config file", terms));
+    }
+    for path in dictionaries {
+        checker = checker.with(Terms::new(&format!("the terms in {}", path), read_terms(path)?));
+    }
+    Ok(checker)
+}
+
+/// Reads a phrase without echoing it from a terminal, or else a line of stdin
+fn read_phrase(prompt: &str) -> Result<String> {
+    let phrase = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password(prompt)?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        line.trim_end_matches(&['\r', '\n'][..]).to_string()
+    };
+    if phrase.is_empty() {
+        bail!("No passphrase given");
+    }
+    Ok(phrase)
+}
+
+/// Prints the guesses an attacker who tries patterns first needs, how long
+/// they take under each attack model and lockout policy and the patterns
+/// found. Unless `reveal`, patterns are named by kind only, so their text
+/// stays private.
+pub fn print_estimate(assessment: &Assessment, locale: &Locale, units: Units, reveal: bool, lockouts: &[Lockout]) {
+    let guesses = assessment.guesses();
+    println!("{}: {} ({:.2} bits)", locale.pattern_guesses, locale.number(guesses, units), assessment.bits);
+    for (attack, name) in ATTACKS.iter().zip(locale.attacks) {
+        // Throttled rates read better per hour
+        let rate = if attack.guesses_per_second < 1.0 {
+            format!("{} {}", locale.number(attack.guesses_per_second * 3600.0, units), locale.guesses_per_hour)
+        } else {
+            format!("{} {}", locale.number(attack.guesses_per_second, units), locale.guesses_per_second)
+        };
+        println!("    {} {} ({}): {}", locale.average_time, name, rate,
+            locale.duration(guesses / 2.0 / attack.guesses_per_second, units));
+    }
+    for lockout in lockouts {
+        println!("    {} {} {}: {}", locale.average_time, locale.locked_out, locale.lockout(lockout),
+            locale.duration(lockout.average_seconds(guesses), units));
+    }
+    if assessment.findings.is_empty() {
+        println!("Patterns found: none");
+    } else if reveal {
+        println!("Patterns found:");
+        for finding in &assessment.findings {
+            println!("    The phrase {}.", finding.weakness);
+        }
+    } else {
+        let kinds: Vec<&str> = assessment.findings.iter().map(|finding| finding.weakness.kind()).collect();
+        println!("Patterns found: {}", kinds.join(", "));
+    }
+}
+
+/// Reads a phrase from stdin, without echoing it, and estimates how long it
+/// would take to guess, also under each of `lockouts`
+pub fn check_command(terms: &[String], dictionaries: &[String], units: Units, lockouts: &[Lockout]) -> Result<()> {
+    let checker = checker(terms, dictionaries)?;
+    let assessment = checker.assess(&read_phrase("Passphrase to check: ")?);
+    println!("Entropy (attacker brute forces its characters): {:.2}", assessment.brute_force_bits);
+    print_estimate(&assessment, Locale::from_env(), units, true, lockouts);
+    Ok(())
+}
+
+/// Reads a phrase someone made up, without echoing it, and points out the
+/// ways it is weaker than it looks. `terms` are the config file's words an
+/// attacker would know to try, and `dictionaries` files of more.
+pub fn lint_command(terms: &[String], dictionaries: &[String]) -> Result<()> {
+    // Before asking, so a missing file is not found after typing the phrase
+    let checker = checker(terms, dictionaries)?;
+    let assessment = checker.assess(&read_phrase("Passphrase to lint: ")?);
+    for finding in &assessment.findings {
+        println!("The phrase {}.", finding.weakness);
+        if finding.naive_bits > 0.0 {
+            println!("    Worth about {:.1} bits to an attacker who tries it, not {:.1}.", finding.bits, finding.naive_bits);
+        }
+        println!("    {}", finding.weakness.suggestion());
+    }
+    println!("Breakdown:");
+    let width = assessment.segments.iter().map(|segment| segment.text.chars().count()).max().unwrap_or(0) + 2;
+    for segment in &assessment.segments {
+        println!("    {:<width$} {:<26} ~{:.1} bits", format!("\"{}\"", segment.text), segment.kind, segment.bits, width = width);
+    }
+    println!("Guess resistance: about {:.1} bits ({:.1} if every character were random)",
+        assessment.bits, assessment.brute_force_bits);
+
+    if assessment.findings.is_empty() {
+        println!("No common weaknesses found. That does not make it strong: a phrase you chose is still easier to guess than a generated one.");
+        return Ok(());
+    }
+    bail!("Found {} weakness(es)", assessment.findings.len());
+}
@@ -1,10 +1,110 @@
-use anyhow::Result;
-use clipboard::{ClipboardProvider, ClipboardContext};
-use clap::Parser;
-use rand::prelude::*;
-use thiserror::Error;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+use std::time::Duration;
 
-const DEFAULT_LIST: &str = "eff_large_wordlist.txt";
+use anyhow::{anyhow, bail, Result};
+use clap::{ArgEnum, ArgMatches, FromArgMatches, IntoApp, Parser, Subcommand};
+use serde_json::json;
+
+use passphrs::case::{self, Case, CaseMask, Language, Style};
+use passphrs::pattern::{self, Pattern};
+use passphrs::constraint::{self, CharClass, Policy};
+use passphrs::strength::{brute_force_entropy, Checker, Lockout};
+use passphrs::wordlist::{self, List};
+use passphrs::transform::{self, SubstitutionMap, Truncate};
+use passphrs::{join_parts, BuildError, Part, PassphraseBuilder};
+
+mod audit;
+mod backup;
+mod bip39;
+mod checksum;
+mod clipboard;
+use clipboard::{Clipboard, Fallback};
+mod cliplint;
+mod color;
+use color::ColorChoice;
+mod compat;
+use compat::Compat;
+mod completions;
+mod config;
+mod confirm;
+mod countdown;
+mod derive;
+mod detach;
+mod dice;
+mod display;
+use display::Display;
+mod external;
+mod fd;
+mod fido2;
+mod fingerprint;
+mod format;
+use format::Format;
+mod health;
+mod hibp;
+#[cfg(any(test, feature = "kat"))]
+mod kat;
+mod keyfile;
+mod labels;
+mod lint;
+mod pick;
+use keyfile::Kdf;
+mod preset;
+use preset::Preset;
+mod report;
+mod scan;
+mod shell;
+use shell::Shell;
+mod teach;
+mod tui;
+mod units;
+use units::{Locale, Units};
+mod version;
+mod wizard;
+
+/// Passphrases --hibp generates before giving up on one not found in breaches
+const MAX_HIBP_ATTEMPTS: usize = 5;
+/// How long the clipboard holds a passphrase under --screenshare, regardless of --wait
+const SCREENSHARE_WAIT: Duration = Duration::from_secs(10);
+/// Longest --wait honoured unless the config file sets max-wait, so a typo
+/// cannot leave a passphrase in the clipboard for days
+const MAX_WAIT: Duration = Duration::from_secs(60 * 60);
+/// What --info shows for the sample under --hide-structure
+const HIDDEN_SAMPLE: &str = "(hidden)";
+/// Guess rate of a well-resourced offline attack against a fast hash
+const OFFLINE_GUESSES_PER_SEC: f64 = 1e12;
+
+fn parse_wait(s: &str) -> Result<Duration, String> {
+    match s.parse::<u64>() {
+        Ok(seconds) => Ok(Duration::from_secs(seconds)),
+        Err(_) => humantime::parse_duration(s).map_err(|e| e.to_string()),
+    }
+}
+
+fn parse_lockout(s: &str) -> Result<Lockout, String> {
+    let (tries, window) = s.split_once('/').ok_or("expected TRIES/WINDOW, e.g. 5/15m")?;
+    let tries = match tries.parse() {
+        Ok(tries) if tries > 0 => tries,
+        _ => return Err(format!("invalid number of tries: {}", tries)),
+    };
+    Ok(Lockout { tries, window: parse_wait(window)? })
+}
+
+fn parse_bits(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(bits) if bits.is_finite() && bits > 0.0 => Ok(bits),
+        Ok(_) => Err(format!("{} is not a positive, finite number of bits", s)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_slot_list(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => Ok((name.to_string(), path.to_string())),
+        _ => Err("expected NAME=FILE, e.g. noun=nouns.txt".to_string()),
+    }
+}
 
 #[derive(Clone, Debug, Parser)]
 #[clap(author, version, about, long_about=None)]
@@ -18,18 +118,190 @@ struct Cli {
     #[clap(short, long, parse(from_flag))]
     info: bool,
 
-    /// Duration to wait before clearing clipboard
-    #[clap(default_value_t = 5, short, long, parse(try_from_str))]
-    wait: u64,
+    /// Number of independent passphrases. Printed to a terminal they are
+    /// numbered in columns, and without --print one is chosen to copy.
+    #[clap(default_value_t = 1, short = 'n', long, parse(try_from_str))]
+    count: usize,
+
+    /// Copies the Nth of the --count passphrases instead of asking which
+    #[clap(long, value_name="N", parse(try_from_str), requires = "count",
+        conflicts_with_all = &["print", "info", "format", "screenshare"])]
+    pick: Option<usize>,
+
+    /// How the --count passphrases are laid out
+    #[clap(default_value = "auto", long, arg_enum)]
+    layout: pick::Layout,
+
+    /// Writes the passphrase to stdout instead of the clipboard, for piping
+    #[clap(long, alias = "stdout", parse(from_flag), conflicts_with_all = &["info", "format", "screenshare"])]
+    print: bool,
+
+    /// Copies through the terminal with the OSC 52 escape sequence, e.g. over SSH or in tmux
+    #[clap(long, parse(from_flag), conflicts_with_all = &["info", "format", "print"])]
+    osc52: bool,
+
+    /// Rings the terminal bell when the passphrase is copied and again when the clipboard is cleared
+    #[clap(long, parse(from_flag), conflicts_with_all = &["info", "format", "print"])]
+    bell: bool,
+
+    /// Shows a countdown to the clipboard clearing in the terminal title
+    #[clap(long, parse(from_flag), conflicts_with_all = &["info", "format", "print"])]
+    title_countdown: bool,
+
+    /// What to do when there is no clipboard, as in a Windows CI job or SSH session
+    #[clap(default_value = "none", long, arg_enum)]
+    clipboard_fallback: Fallback,
+
+    /// Copies without ever printing the passphrase or its structure and clears
+    /// the clipboard after a short fixed window, for generating on a call
+    #[clap(long, parse(from_flag), conflicts_with_all = &["info", "format"])]
+    screenshare: bool,
+
+    /// With --info, shows a real sample instead of placeholders
+    #[clap(long, parse(from_flag))]
+    show_sample: bool,
+
+    /// With --info and --show-sample, lists each word's index in the word list
+    /// and the dice rolls that select it
+    #[clap(long, parse(from_flag), requires_all = &["info", "show-sample"], conflicts_with = "hide-structure")]
+    show_indices: bool,
+
+    /// With --info, reports which word the salt was added to
+    #[clap(long, parse(from_flag), conflicts_with = "hide-structure")]
+    salt_position_report: bool,
+
+    /// Never emit structural hints: no tinted parts, boundary markers, salt
+    /// position, word indices or placeholder shape
+    #[clap(long, parse(from_flag))]
+    hide_structure: bool,
+
+    /// Which attacker the --info entropy figure assumes
+    #[clap(default_value = "known-parameters", long, arg_enum)]
+    entropy_model: EntropyModel,
+
+    /// With --info, also shows the sample in another form
+    #[clap(default_value = "plain", long, arg_enum)]
+    display: Display,
+
+    /// Wraps --display braille into lines of this many cells for embossing
+    #[clap(long, value_name="CELLS", parse(try_from_str))]
+    braille_width: Option<usize>,
+
+    /// With --info, compares the entropy against familiar kinds of secret
+    #[clap(long, parse(from_flag))]
+    visual: bool,
+
+    /// Duration to wait before clearing clipboard, e.g. "90s" or "2m". A bare
+    /// number is in seconds and 0 never clears.
+    #[clap(default_value = "5", short, long, parse(try_from_str = parse_wait))]
+    wait: Duration,
+
+    /// Empties the clipboard after --wait instead of putting back what it held before
+    #[clap(long, parse(from_flag))]
+    no_restore: bool,
+
+    /// Prints or copies the passphrase quoted as one argument for this shell
+    #[clap(long, arg_enum, value_name="SHELL", conflicts_with_all = &["info", "format"])]
+    shell_quote: Option<Shell>,
+
+    /// Returns to the shell at once, leaving a background process to clear the clipboard
+    #[clap(long, parse(from_flag), conflicts_with = "print")]
+    detach: bool,
+
+    /// Estimates how long a passphrase read from stdin would take to guess,
+    /// instead of generating one
+    #[clap(long, parse(from_flag),
+        conflicts_with_all = &["info", "format", "count", "interactive", "dice", "print", "screenshare"])]
+    check: bool,
+
+    /// File of terms an attacker may know, for --check as for lint. Can be
+    /// given more than once.
+    #[clap(long, value_name="FILE", multiple_occurrences = true, requires = "check")]
+    dictionary: Vec<String>,
 
-    /// Sets passphrase length
+    /// With --info or --check, also estimates online guessing against an
+    /// account that locks after TRIES failures until WINDOW has passed, e.g.
+    /// 5/15m. Can be given more than once.
+    #[clap(long, value_name="TRIES/WINDOW", multiple_occurrences = true, parse(try_from_str = parse_lockout))]
+    lockout: Vec<Lockout>,
+
+    /// Writes the passphrase to this inherited file descriptor and nowhere
+    /// else, for a program that runs passphrs and reads it from a pipe
+    #[clap(long, value_name="N", parse(try_from_str),
+        conflicts_with_all = &["print", "detach", "shell-quote", "info", "format", "count", "screenshare"])]
+    fd: Option<u32>,
+
+    /// Choose among candidates in the terminal, changing length and separator on the fly
+    #[clap(short = 'I', long, parse(from_flag), conflicts_with_all = &["info", "format", "count", "screenshare"])]
+    interactive: bool,
+
+    /// Picks words with your own dice rolls, one per --length word, read from
+    /// stdin, instead of the random number generator. Needs a
+    /// diceware-numbered list and adds no salt.
+    #[clap(long, parse(from_flag),
+        conflicts_with_all = &["info", "format", "count", "interactive", "bits", "case-mask", "checksum"])]
+    dice: bool,
+
+    /// Generates a BIP39 mnemonic of this many words, with its checksum,
+    /// instead of a passphrase. Word list and casing settings do not apply.
+    #[clap(long, value_name="WORDS", possible_values = &bip39::LENGTHS, parse(try_from_str),
+        conflicts_with_all = &["info", "count", "interactive", "dice", "check"])]
+    bip39: Option<usize>,
+
+    /// What to build the passphrase from. With pronounceable, --length counts syllables.
+    #[clap(default_value = "words", long, arg_enum, conflicts_with = "path")]
+    mode: Mode,
+
+    /// Sets passphrase length. 0 generates only salt.
     #[clap(default_value_t = 7, short, long, parse(try_from_str))]
     length: usize,
 
-    /// Sets separator between words
+    /// Uses the fewest words that reach this many bits of entropy, instead of --length
+    #[clap(long, value_name="BITS", conflicts_with = "length", parse(try_from_str = parse_bits))]
+    bits: Option<f64>,
+
+    /// Never uses the same word twice in one passphrase, which costs a little entropy
+    #[clap(long, parse(from_flag), conflicts_with_all = &["dice", "bip39"])]
+    unique: bool,
+
+    /// Picks words so that words, separators and salt come to about this many characters, uniformly among the passphrases that do
+    #[clap(long, value_name="CHARS", parse(try_from_str),
+        conflicts_with_all = &["truncate", "pattern", "template", "unique", "dice", "bip39"])]
+    balance_length: Option<usize>,
+
+    /// How far from --balance-length the passphrase may be, in characters
+    #[clap(default_value_t = 3, long, value_name="CHARS", parse(try_from_str))]
+    balance_tolerance: usize,
+
+    /// Replaces letters at random with look-alikes, as in p@ssw0rd, for policies that demand symbols
+    #[clap(long, parse(from_flag), conflicts_with_all = &["case-mask", "dice", "bip39", "pattern"])]
+    substitute: bool,
+
+    /// What --substitute replaces, as LETTER=REPLACEMENT pairs
+    #[clap(long, value_name="MAP", default_value = transform::DEFAULT_SUBSTITUTIONS,
+        parse(try_from_str = transform::parse_substitutions))]
+    substitutions: SubstitutionMap,
+
+    /// Probability that --substitute replaces each letter it can
+    #[clap(default_value_t = 0.5, long, parse(try_from_str = case::parse_prob))]
+    substitute_prob: f64,
+
+    /// Cuts every word to its first N characters, which costs entropy where words share a prefix
+    #[clap(long, value_name="N", parse(try_from_str))]
+    truncate: Option<usize>,
+
+    /// Picks each separator between words at random from these characters, e.g. "-_.,"
+    #[clap(long, value_name="CHARS", allow_hyphen_values = true, conflicts_with_all = &["separator", "dice", "bip39", "pattern"])]
+    separator_set: Option<String>,
+
+    /// Sets separator between words. "none" joins words directly.
     #[clap(default_value = " ", short, long)]
     separator: String,
 
+    /// Marks word boundaries in the --info sample when words are joined directly
+    #[clap(long, parse(from_flag))]
+    boundaries: bool,
+
     /// Set salt length
     #[clap(default_value_t = 1, long = "sl", parse(try_from_str))]
     salt_length: usize,
@@ -38,164 +310,1324 @@ struct Cli {
     #[clap(default_value = "0123456789", long = "sc")]
     salt_chars: String,
 
-    /// Set word case. 0: no processing, 1: lowercase, 2: capitalized, 3: uppercase
-    #[clap(default_value_t = 2, short, long, parse(try_from_str))]
-    case: usize,
+    /// Set word case. camel and pascal join words unless a separator is set; random adds entropy. 0-3 still mean none, lower, capitalized and upper.
+    #[clap(default_value = "capitalized", short, long, arg_enum)]
+    case: Style,
+
+    /// Set a per-letter case mask, overriding case. U: upper, l: lower, ?: random, .: unchanged. The last position repeats.
+    #[clap(long, value_name="MASK", parse(try_from_str = case::parse_mask))]
+    case_mask: Option<CaseMask>,
+
+    /// Probability that a random case mask position is uppercased
+    #[clap(default_value_t = 0.5, long, parse(try_from_str = case::parse_prob))]
+    case_prob: f64,
+
+    /// Language whose casing rules apply, e.g. turkish so that i capitalizes to İ
+    #[clap(default_value = "unicode", long, arg_enum)]
+    case_language: Language,
+
+    /// Require the passphrase to start with a character of this class
+    #[clap(long, arg_enum, value_name="CLASS")]
+    must_start_with: Option<CharClass>,
+
+    /// Require the passphrase to end with a character of this class
+    #[clap(long, arg_enum, value_name="CLASS")]
+    must_end_with: Option<CharClass>,
+
+    /// Require at least one character of each of these classes, e.g. digit,upper,symbol.
+    /// Digits and symbols are added to the salt if nothing else supplies them.
+    #[clap(long, arg_enum, value_name="CLASSES", use_delimiter = true)]
+    require: Vec<CharClass>,
+
+    /// Reject passphrases longer than this many characters
+    #[clap(long, value_name="N", parse(try_from_str))]
+    max_chars: Option<usize>,
+
+    /// Reject passphrases shorter than this many characters
+    #[clap(long, value_name="N", parse(try_from_str))]
+    min_chars: Option<usize>,
+
+    /// Guarantee none of these characters appear in words, salt or separator
+    #[clap(long, value_name="CHARS")]
+    forbid_chars: Option<String>,
+
+    /// Use defaults suited to a kind of secret. Explicit flags take precedence.
+    #[clap(long, arg_enum)]
+    preset: Option<Preset>,
+
+    /// Use settings saved under this name in the config file, e.g. by the wizard
+    #[clap(long, value_name="NAME")]
+    profile: Option<String>,
+
+    /// Ids of the settings the config file's defaults supplied
+    #[clap(skip)]
+    config_settings: Vec<&'static str>,
+
+    /// Ids of the settings the profile supplied
+    #[clap(skip)]
+    profile_settings: Vec<&'static str>,
+
+    /// Longest --wait allowed, from max-wait in the config file
+    #[clap(skip)]
+    max_wait: Option<Duration>,
+
+    /// Never include this account name, and meet Windows complexity rules as --preset ad does
+    #[clap(long)]
+    username: Option<String>,
+
+    /// Prepend a fixed identifying prefix, which adds no entropy
+    #[clap(long)]
+    prefix: Option<String>,
+
+    /// Append a CRC32 check segment so mistyped tokens can be detected
+    #[clap(long, parse(from_flag))]
+    checksum: bool,
+
+    /// Keep salted fingerprints of generated passphrases and warn on repeats.
+    /// Derived passphrases and dice rolls repeat by design and are not kept.
+    #[clap(long, parse(from_flag))]
+    track_duplicates: bool,
+
+    /// Regenerate until Have I Been Pwned has never seen the passphrase,
+    /// sending only part of its hash via curl. Skipped with a warning if
+    /// the API cannot be reached.
+    #[clap(long, parse(from_flag))]
+    hibp: bool,
+
+    /// Fail instead of skipping when --hibp cannot reach the API
+    #[clap(long, parse(from_flag), requires = "hibp")]
+    hibp_required: bool,
 
-    /// Use a custom word list at the given location
+    /// Refuse to generate if the system random number generator looks unhealthy
+    #[clap(long, parse(from_flag))]
+    strict_entropy: bool,
+
+    /// Also derive raw key bytes from the passphrase with this function
+    #[clap(long, arg_enum, value_name="KDF", requires = "out")]
+    to_keyfile: Option<Kdf>,
+
+    /// Number of key bytes to derive with --to-keyfile
+    #[clap(default_value_t = 32, long, parse(try_from_str))]
+    bytes: usize,
+
+    /// File to write the derived key to, readable only by the current user.
+    /// Its salt and parameters go next to it in FILE.params.
+    #[clap(long, value_name="FILE")]
+    out: Option<String>,
+
+    /// Initialize a restic repository encrypted with the passphrase
+    #[clap(long, value_name="REPO", conflicts_with = "init-borg")]
+    init_restic: Option<String>,
+
+    /// Initialize a borg repository encrypted with the passphrase
+    #[clap(long, value_name="REPO")]
+    init_borg: Option<String>,
+
+    /// Print the passphrase embedded in a credential line instead of copying it
+    #[clap(long, arg_enum)]
+    format: Option<Format>,
+
+    /// Host the credential is for, used by --format
+    #[clap(long)]
+    host: Option<String>,
+
+    /// User name the credential is for, used by --format
+    #[clap(long)]
+    login: Option<String>,
+
+    /// Port the credential is for, used by --format
+    #[clap(long, parse(try_from_str))]
+    port: Option<u16>,
+
+    /// Database the credential is for, used by --format
+    #[clap(long)]
+    database: Option<String>,
+
+    /// Variable or secret name, used by --format dotenv and github-secret
+    #[clap(long)]
+    key: Option<String>,
+
+    /// Checks every destination and generates a passphrase, but writes and
+    /// prints nothing secret
+    #[clap(long, parse(from_flag))]
+    dry_run: bool,
+
+    /// Write to restic, borg or GitHub without asking to confirm the destination
+    #[clap(short, long, parse(from_flag))]
+    yes: bool,
+
+    /// Overwrite the clipboard even if it seems to hold another secret
+    #[clap(long, parse(from_flag))]
+    force: bool,
+
+    /// Use one of the word lists built into passphrs
+    #[clap(default_value = "eff-large", long, arg_enum)]
+    list: List,
+
+    /// Use a custom word list at the given location instead of --list
     #[clap(short, long, value_name="FILE")]
     path: Option<String>,
 
+    /// Draws each word from its own list, named by --slot-list or word for the main one, e.g. adj,noun,verb,noun. Sets the length.
+    #[clap(long, value_name="SLOTS", use_delimiter = true,
+        conflicts_with_all = &["length", "bits", "dice", "bip39", "unique", "interactive", "truncate", "mode"])]
+    template: Vec<String>,
+
+    /// Spells out the passphrase: W a word, w a lowercase word, d a digit, s a symbol, # a salt character, e.g. Wd-Ww-###. Anything else, or anything after \, is kept as is.
+    #[clap(long, parse(try_from_str = pattern::parse), conflicts_with_all = &["length", "bits", "template", "unique",
+        "dice", "bip39", "truncate", "interactive", "case-mask", "mode", "must-start-with", "must-end-with", "salt-length"])]
+    pattern: Option<Pattern>,
+
+    /// A word list for --template, as NAME=FILE, e.g. adj=adjectives.txt. Repeat for each name.
+    #[clap(long, value_name="NAME=FILE", multiple_occurrences = true, parse(try_from_str = parse_slot_list))]
+    slot_list: Vec<(String, String)>,
+
     /// Don't process the word list. Disables case.
     #[clap(long, parse(from_flag))]
     raw: bool,
+
+    /// Only use words with at least this many characters, e.g. to drop very short ones
+    #[clap(long, parse(try_from_str))]
+    min_word_len: Option<usize>,
+
+    /// Only use words with at most this many characters, e.g. for typing on a phone
+    #[clap(long, parse(try_from_str))]
+    max_word_len: Option<usize>,
+
+    /// Interpret all flags after this one using another generator's conventions
+    #[clap(long, arg_enum)]
+    compat: Option<Compat>,
+
+    /// Colorize terminal output. NO_COLOR disables color in auto mode.
+    #[clap(default_value = "auto", long, arg_enum)]
+    color: ColorChoice,
+
+    /// How large numbers and durations are written in --info
+    #[clap(default_value = "human", long, arg_enum)]
+    units: Units,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Debug, Subcommand)]
+enum Command {
+    /// Check the checksum of a token made with --checksum
+    VerifyToken {
+        /// Token to check. Read from stdin if omitted.
+        token: Option<String>,
+    },
+    /// Print a numbered handout of practice passphrases for a training session
+    Teach {
+        /// Number of distinct passphrases to print
+        #[clap(default_value_t = 30, long, parse(try_from_str))]
+        students: usize,
+    },
+    /// Print unique, numbered device credentials as labels or CSV, e.g. with --preset device
+    Labels {
+        /// Number of devices
+        #[clap(default_value_t = 10, long, parse(try_from_str))]
+        count: usize,
+
+        /// Text before each device number
+        #[clap(default_value = "device-", long)]
+        label_prefix: String,
+
+        /// Number of the first device
+        #[clap(default_value_t = 1, long, parse(try_from_str))]
+        start: usize,
+
+        /// Print label,passphrase CSV instead of printable labels
+        #[clap(long)]
+        csv: bool,
+    },
+    /// Answer a Terraform external data source query read from stdin with the
+    /// passphrase derived for its label from the master secret in
+    /// PASSPHRS_MASTER_SECRET, so it is the same on every plan
+    ExternalData {
+        /// Taken from the query's label
+        #[clap(hide = true)]
+        label: Option<String>,
+    },
+    /// Look for text shaped like generated passphrases, e.g. in a pre-commit hook
+    Scan {
+        /// Scan lines added in the staged git diff instead of stdin
+        #[clap(long, conflicts_with = "csv")]
+        git_staged: bool,
+
+        /// Report reused passphrases in a hashed credential export instead
+        #[clap(long, value_name="FILE")]
+        csv: Option<String>,
+
+        /// Consecutive word list words needed to report a match. Without it,
+        /// five are needed, or three with digits or symbols for salt, or four
+        /// shaped like --preset token.
+        #[clap(long, parse(try_from_str))]
+        min_words: Option<usize>,
+    },
+    /// Answer a few questions about a site, save settings that suit it as a
+    /// profile and generate with them
+    Wizard {
+        /// Name to save the profile as
+        name: String,
+    },
+    /// Derive the passphrase for a site from a master secret, read without
+    /// echoing, instead of choosing it at random. The same secret, label and
+    /// settings always give the same passphrase.
+    Derive {
+        /// Site or account the passphrase is for, e.g. example.com
+        label: String,
+
+        /// Also binds the passphrase to the security key at DEVICE, e.g.
+        /// /dev/hidraw0, through the FIDO2 hmac-secret extension, so it
+        /// cannot be derived again without the key
+        #[clap(long, value_name="DEVICE", requires = "credential")]
+        fido2: Option<String>,
+
+        /// Base64 id of an hmac-secret credential on the key for relying
+        /// party passphrs, as made by fido2-cred -M -h
+        #[clap(long, value_name="ID", requires = "fido2")]
+        credential: Option<String>,
+    },
+    /// Turn a file of recorded dice rolls into the passphrase they select
+    /// from the word list, as --dice does with rolls typed in
+    FromRolls {
+        /// Rolls separated by whitespace; anything after a # is ignored
+        file: String,
+    },
+    /// Inspect word lists
+    Wordlist {
+        #[clap(subcommand)]
+        command: WordlistCommand,
+    },
+    /// Report where a word is in the word list and the dice roll that selects it
+    Lookup {
+        word: String,
+    },
+    /// Print a shell completion script
+    Completions {
+        #[clap(arg_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Point out weaknesses in a passphrase you made up, read without echoing
+    Lint {
+        /// File of terms an attacker may know, one per line, such as employer
+        /// names, usernames or emails. Can be given more than once.
+        #[clap(long, value_name="FILE", multiple_occurrences = true)]
+        dictionary: Vec<String>,
+    },
+    /// Check that this build generates exactly the passphrases upstream does
+    #[cfg(feature = "kat")]
+    Kat,
+    /// Hold and later clear the clipboard for --detach
+    #[clap(setting = clap::AppSettings::Hidden)]
+    ClipboardWorker,
+    /// Print version information
+    Version {
+        /// Print build provenance as JSON
+        #[clap(short, long)]
+        verbose: bool,
+    },
 }
 
-fn get_list(path: Option<&String>, raw: bool)
-    -> Result<Vec<String>> {
+#[derive(Clone, Debug, Subcommand)]
+enum WordlistCommand {
+    /// Compare the bundled lists, --path and any FILEs by bits per typed
+    /// character, separator included
+    Audit {
+        /// More word lists to compare
+        #[clap(value_name="FILE")]
+        files: Vec<String>,
+    },
+}
+
+/// Opens a file readable only by the current user where supported
+fn open_private(path: &std::path::Path, options: &mut std::fs::OpenOptions) -> std::io::Result<std::fs::File> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+fn get_list(path: Option<&String>, list: List, raw: bool) -> Result<Vec<String>> {
     let file: String = if let Some(path_) = path {
-        println!("Reading word list from {}...", path_);
+        eprintln!("Reading word list from {}...", path_);
         std::fs::read_to_string(path_)?
     } else {
-        std::fs::read_to_string(DEFAULT_LIST)?
+        list.text().to_string()
     };
 
-    let i_list: Vec<&str> = file.lines().collect();
-    let o_list: Vec<String>;
-    if !raw {
-        o_list = i_list.iter()
-            .map(|w| w.chars().filter(|c| c.is_alphabetic()).collect::<String>()
-                 .to_ascii_lowercase())
-            .filter(|w| w.len() > 0)
-            .collect()
-    } else {
-        o_list = i_list.iter()
-            .map(|w| w.trim().to_string())
-            .filter(|w| w.len() > 0)
-            .collect()
-    }
+    let o_list = wordlist::parse(&file, raw, None, None);
 
-    if o_list.len() == 0 {
-        eprintln!("Word list has no words!");
+    if o_list.is_empty() {
+        bail!("The word list has no words");
     }
     Ok(o_list)
 }
 
-fn build_passphrase(
-    list: &Vec<String>, 
-    length: usize, 
-    separator: &str, 
-    salt_length: usize,
-    salt_chars: &str,
-    case: usize,
-) -> String {
-    let mut rng = rand::prelude::thread_rng();
-    let salt_pos = rng.gen_range(0..length);
-    let mut phrase = String::new();
-    for i in 0..length {
-        if i == 0 {
-            let mut word = list[rng.gen_range(0..list.len())].clone();
-            match case {
-                1 => word.make_ascii_lowercase(),
-                2 => { word.get_mut(0..1).unwrap().make_ascii_uppercase()},
-                3 => word.make_ascii_uppercase(),
-                _ => {}
-            };
+/// What a passphrase is made of
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    /// Words from the word list
+    Words,
+    /// Made-up syllables of consonants and vowels, lowercase and unseparated
+    /// unless set otherwise, for sites that ban spaces and long passphrases
+    Pronounceable,
+}
 
-            phrase += word.as_str();
-        } else {
-            let mut word = list[rng.gen_range(0..list.len())].clone();
-            match case {
-                1 => word.make_ascii_lowercase(),
-                2 => { word.get_mut(0..1).unwrap().make_ascii_uppercase()},
-                3 => word.make_ascii_uppercase(),
-                _ => {}
-            };
+/// Which attacker the reported entropy assumes
+#[derive(clap::ArgEnum, Clone, Copy, Debug, PartialEq)]
+enum EntropyModel {
+    /// The attacker knows the word list, length and salt scheme
+    KnownParameters,
+    /// The attacker brute forces characters without knowing how it was made
+    UnknownParameters,
+    Both,
+}
 
-            phrase += separator;
-            phrase += word.as_str();
+/// Describes where the salt landed, by word and by character offset
+fn salt_position(parts: &[Part]) -> String {
+    let mut offset = 0;
+    let mut words = 0;
+    for part in parts {
+        match part {
+            Part::Salt(salt) => {
+                let len = salt.chars().count();
+                let span = if len == 1 {
+                    format!("character {}", offset + 1)
+                } else {
+                    format!("characters {}-{}", offset + 1, offset + len)
+                };
+                return if words == 0 { span } else { format!("after word {} ({})", words, span) };
+            }
+            Part::Word(..) => words += 1,
+            _ => {}
         }
+        offset += part.as_str().chars().count();
+    }
+    "none".to_string()
+}
 
-        if i == salt_pos {
-            for _ in 0..salt_length {
-                phrase.push(salt_chars.chars().nth(rng.gen_range(0..salt_chars.len())).unwrap())
-            }
+/// A fake copy of `parts` with the same shape: word letters become `x` or
+/// `X` following their case, and salt characters become `#`
+fn redact(parts: &[Part]) -> Vec<Part> {
+    let mask = |s: &str, f: fn(char) -> char| s.chars().map(f).collect();
+    parts.iter()
+        .map(|part| match part {
+            Part::Word(w, _) => Part::Word(mask(w, |c| if c.is_uppercase() { 'X' } else { 'x' }), 0),
+            Part::Salt(salt) => Part::Salt(mask(salt, |_| '#')),
+            other => other.clone(),
+        })
+        .collect()
+}
+
+/// The --info sample when no real one is asked for: placeholders in its
+/// shape, or under --hide-structure one that gives away nothing of it
+fn placeholder(parts: &[Part], hide_structure: bool) -> Vec<Part> {
+    if hide_structure { vec![Part::Literal(HIDDEN_SAMPLE.to_string())] } else { redact(parts) }
+}
+
+/// The roll of `dice` dice that selects word `index` of a list of 6^dice
+fn dice_roll(index: usize, dice: u32) -> String {
+    (0..dice).rev().map(|d| char::from(b'1' + (index / 6usize.pow(d) % 6) as u8)).collect()
+}
+
+/// Where `word` is in the active list, and the dice roll that selects it:
+/// the one written beside it in a diceware-numbered list, or else the one
+/// its index gives
+fn lookup(word: &str, word_list: &[String], raw: bool, numbered: Option<dice::Numbered>) -> Result<()> {
+    let wanted = if raw { word.trim().to_string() } else { word.trim().to_lowercase() };
+    let index = match word_list.iter().position(|w| *w == wanted) {
+        Some(index) => index,
+        None => bail!("{} is not in the word list", word),
+    };
+    print!("{}: index {} (line {}) of {} words", wanted, index, index + 1, word_list.len());
+    match (numbered.as_ref().and_then(|list| list.roll(&wanted)), (1..=12).find(|&k| 6usize.pow(k) == word_list.len())) {
+        (Some(roll), _) => println!(", dice roll {}", roll),
+        (None, Some(dice)) => println!(", dice roll {}", dice_roll(index, dice)),
+        (None, None) => println!(", not a power of 6 so no dice roll selects it"),
+    }
+    Ok(())
+}
+
+/// Lists the word list index of each word in `parts`, and the dice rolls it
+/// corresponds to if the list has a power of six words
+fn word_indices(parts: &[Part], list_len: usize) -> Vec<String> {
+    let dice = (1..=12).find(|&k| 6usize.pow(k) == list_len);
+    let mut lines = vec![match dice {
+        Some(k) => format!("Word indices (list of {} words, {} dice per word):", list_len, k),
+        None => format!("Word indices (list of {} words, not a power of 6 so no dice rolls match):", list_len),
+    }];
+
+    for (n, (word, index)) in parts.iter()
+        .filter_map(|part| match part {
+            Part::Word(w, i) => Some((w, *i)),
+            _ => None,
+        })
+        .enumerate()
+    {
+        let rolls = dice.map_or_else(String::new, |k| dice_roll(index, k));
+        lines.push(format!("  {}. {:>6} {:>6}  {}", n + 1, index, rolls, word));
+    }
+    lines
+}
+
+/// The command line spelling of an enum value
+fn arg_name<T: ArgEnum>(value: &T) -> Option<&'static str> {
+    value.to_possible_value().map(|value| value.get_name())
+}
+
+/// The builder's error in terms of the flags that led to it
+fn explain(cli: &Cli, err: BuildError) -> anyhow::Error {
+    let class = |class: &Option<CharClass>| class.as_ref().and_then(arg_name).unwrap_or("");
+    match err {
+        BuildError::PrefixStart(first) => {
+            anyhow!("The prefix starts with '{}', which --must-start-with {} rules out", first, class(&cli.must_start_with))
+        }
+        BuildError::SeparatorsForbidden => anyhow!("--separator-set has no characters that are not forbidden"),
+        BuildError::SubstitutionsForbidden => anyhow!("Every --substitutions replacement has a forbidden character"),
+        BuildError::TooFewWords(length, words) => {
+            anyhow!("--unique needs at least {} words, and the list has {}", length, words)
+        }
+        BuildError::NoBalance(length, target, tolerance) => {
+            anyhow!("No {} words come to {}±{} characters with these settings; change --length or --balance-length",
+                length, target, tolerance)
+        }
+        BuildError::EmptyPhrase => {
+            let rules: Vec<String> = [("start", &cli.must_start_with), ("end", &cli.must_end_with)].iter()
+                .filter(|(_, rule)| rule.is_some())
+                .map(|(side, rule)| format!("meet --must-{}-with {}", side, class(rule)))
+                .collect();
+            anyhow!("An empty passphrase cannot {}; add words with --length or salt with --salt-length", rules.join(" or "))
+        }
+        BuildError::PolicyUnsatisfiable => {
+            anyhow!("Too few passphrases meet --require, --min-chars and --max-chars with these settings; try fewer or more words")
         }
+        err => err.into(),
+    }
+}
+
+/// Where the setting `id` came from
+fn source(cli: &Cli, matches: &ArgMatches, id: &str) -> &'static str {
+    if matches.occurrences_of(id) > 0 {
+        "command line"
+    } else if cli.preset.is_some_and(|preset| preset.settings().contains(&id)) {
+        "preset"
+    } else if cli.profile_settings.contains(&id) {
+        "profile"
+    } else if cli.config_settings.contains(&id) {
+        "config file"
+    } else {
+        "default"
+    }
+}
+
+/// Resolved settings for the JSON report, each with where it came from
+fn configuration(cli: &Cli, matches: &ArgMatches) -> BTreeMap<&'static str, report::Setting> {
+    let source = |id: &str| source(cli, matches, id);
+    let word_list = match &cli.path {
+        Some(path) => json!(path),
+        None => json!(cli.list.name()),
     };
 
-    phrase
+    BTreeMap::from([
+        ("mode", json!(arg_name(&cli.mode))),
+        ("length", json!(cli.length)),
+        ("separator", json!(cli.separator)),
+        ("separator-set", json!(cli.separator_set)),
+        ("salt-length", json!(cli.salt_length)),
+        ("salt-chars", json!(cli.salt_chars)),
+        ("case", json!(arg_name(&cli.case))),
+        ("case-mask", json!(matches.value_of("case-mask"))),
+        ("case-prob", json!(cli.case_prob)),
+        ("list", word_list),
+        ("raw", json!(cli.raw)),
+        ("unique", json!(cli.unique)),
+        ("balance-length", json!(cli.balance_length)),
+        ("substitute", json!(cli.substitute)),
+        ("template", json!(cli.template)),
+        ("pattern", json!(matches.value_of("pattern"))),
+        ("min-word-len", json!(cli.min_word_len)),
+        ("max-word-len", json!(cli.max_word_len)),
+        ("must-start-with", json!(cli.must_start_with.as_ref().and_then(arg_name))),
+        ("must-end-with", json!(cli.must_end_with.as_ref().and_then(arg_name))),
+        ("forbid-chars", json!(cli.forbid_chars)),
+        ("require", json!(cli.require.iter().filter_map(arg_name).collect::<Vec<_>>())),
+        ("min-chars", json!(cli.min_chars)),
+        ("max-chars", json!(cli.max_chars)),
+        ("username", json!(cli.username)),
+        ("prefix", json!(cli.prefix)),
+        ("checksum", json!(cli.checksum)),
+        ("truncate", json!(cli.truncate)),
+        ("case-language", json!(arg_name(&cli.case_language))),
+        ("bits", json!(cli.bits)),
+        ("boundaries", json!(cli.boundaries)),
+        ("balance-tolerance", json!(cli.balance_tolerance)),
+        ("substitutions", json!(matches.value_of("substitutions"))),
+        ("substitute-prob", json!(cli.substitute_prob)),
+        ("slot-list", json!(cli.slot_list.iter().map(|(slot, path)| format!("{}={}", slot, path)).collect::<Vec<_>>())),
+        ("hibp", json!(cli.hibp)),
+        ("preset", json!(cli.preset.as_ref().and_then(arg_name))),
+        ("profile", json!(cli.profile)),
+    ])
+    .into_iter()
+    .map(|(id, value)| {
+        let id_source = if id == "list" && cli.path.is_some() { "path" } else { id };
+        (id, report::Setting { value, source: source(id_source) })
+    })
+    .collect()
 }
 
-fn entropy(
-    list_len: usize,
-    phrase_len: usize,
-    salt_len: usize,
-    salt_chars: &String,
-) -> (f64, f64) {
-    // N is the total number of valid combinations
-    let mut c: f64 = (list_len as f64).powi(phrase_len as i32);
-    if salt_len > 0 {
-        c *= (phrase_len * (salt_chars.len().pow(salt_len as u32))) as f64;
+/// The clipboard the passphrase goes to, or None when it is printed or
+/// written to --fd instead
+fn open_clipboard(cli: &Cli) -> Result<Option<Clipboard>> {
+    if cli.fd.is_some() || cli.print {
+        Ok(None)
+    } else if cli.osc52 {
+        Ok(Some(Clipboard::osc52()?))
+    } else {
+        Clipboard::with_fallback(cli.clipboard_fallback)
     }
+}
 
-    let entropy = (c as f64).log2();
-    (entropy, entropy / 7.0)
+/// Runs every check the requested outputs would make and generates a
+/// passphrase, reporting what would happen without writing anything
+fn dry_run(cli: &Cli, target: &format::Target, generate: &dyn Fn() -> Result<Vec<Part>>) -> Result<()> {
+    let phrase = join_parts(&generate()?);
+    match cli.format {
+        Some(Format::GithubSecret) => {
+            format::check_github_secret(target)?;
+            eprintln!("Would set GitHub Actions secret {}", cli.key.as_deref().unwrap_or(""));
+        }
+        Some(format) => {
+            format::render(format, &phrase, target)?;
+            eprintln!("Would print a {:?} line", format);
+        }
+        None => match (cli.fd, open_clipboard(cli)?) {
+            (Some(fd), _) => {
+                fd::check(fd)?;
+                eprintln!("Would write the passphrase to file descriptor {}", fd);
+            }
+            (None, Some(Clipboard::Osc52(_))) => eprintln!("Would copy the passphrase through the terminal with OSC 52"),
+            (None, Some(Clipboard::ClipExe)) => eprintln!("Would copy the passphrase with clip.exe"),
+            (None, Some(_)) => eprintln!("Would copy the passphrase to the clipboard"),
+            (None, None) if cli.screenshare => {
+                bail!("--screenshare never prints the passphrase, and there is no clipboard to copy it to")
+            }
+            (None, None) => eprintln!("Would print the passphrase"),
+        },
+    }
+    if let (Some(_), Some(out)) = (cli.to_keyfile, &cli.out) {
+        keyfile::check(out)?;
+        eprintln!("Would write {} key bytes to {}", cli.bytes, out);
+    }
+    if let Some(repo) = &cli.init_restic {
+        backup::check(backup::Tool::Restic, repo)?;
+        eprintln!("Would initialize restic repository {}", repo);
+    }
+    if let Some(repo) = &cli.init_borg {
+        backup::check(backup::Tool::Borg, repo)?;
+        eprintln!("Would initialize borg repository {}", repo);
+    }
+    eprintln!("Dry run: nothing was written");
+    Ok(())
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut matches = Cli::into_app().get_matches_from(compat::translate(std::env::args().collect())?);
+    let mut cli = Cli::from_arg_matches(&matches)?;
+    if let Some(Command::ExternalData { .. }) = cli.command {
+        matches = Cli::into_app().try_get_matches_from(external::query_args(&Cli::into_app())?)?;
+        cli = Cli::from_arg_matches(&matches)?;
+    }
+    if let Some(Command::Wizard { name }) = &cli.command {
+        let name = name.clone();
+        let path = config::save_profile(&name, wizard::run()?)?;
+        eprintln!("Saved profile {} to {}. Use it again with --profile {}", name, path.display(), name);
+        cli.profile = Some(name);
+    }
+    let config = config::load()?;
+    cli.config_settings = config.defaults.apply(&mut cli, &matches)?;
+    if let Some(name) = cli.profile.clone() {
+        cli.profile_settings = config.profile(&name)?.apply(&mut cli, &matches)?;
+    }
+    if let Some(preset) = cli.preset {
+        preset.apply(&mut cli, &matches);
+    }
 
-    let wait = cli.wait.clone();
-    let length = cli.length.clone();
-    let separator = cli.separator.clone();
-    let salt_length = cli.salt_length.clone();
+    match &cli.command {
+        Some(Command::VerifyToken { token }) => return checksum::verify_command(token.as_ref()),
+        Some(Command::Version { verbose }) => return version::version_command(*verbose),
+        Some(Command::ClipboardWorker) => return detach::worker(),
+        Some(Command::Lint { dictionary }) => return lint::lint_command(&config.terms, dictionary),
+        Some(Command::Completions { shell }) => return completions::completions_command(*shell),
+        Some(Command::Wordlist { command: WordlistCommand::Audit { files } }) => {
+            let parse = |text: &str| wordlist::parse(text, cli.raw, cli.min_word_len, cli.max_word_len);
+            let mut lists: Vec<(String, Vec<String>)> = wordlist::BUNDLED.iter()
+                .map(|list| (list.name().to_string(), parse(list.text())))
+                .collect();
+            lists.push(("syllables (--mode pronounceable)".to_string(), wordlist::syllables()));
+            let mut public: Vec<(String, Vec<String>)> = wordlist::BUNDLED.iter()
+                .map(|list| (list.name().to_string(), wordlist::parse(list.text(), false, None, None)))
+                .collect();
+            public.push((bip39::LIST_NAME.to_string(), bip39::words()));
+            let mut custom = Vec::new();
+            for path in cli.path.iter().chain(files) {
+                custom.push((path.clone(), parse(&std::fs::read_to_string(path)?)));
+            }
+            lists.extend(custom.iter().cloned());
+            let separator = if cli.separator == "none" { "" } else { &cli.separator };
+            return audit::audit_command(&lists, &custom, &public, separator, cli.length);
+        }
+        #[cfg(feature = "kat")]
+        Some(Command::Kat) => return kat::kat_command(),
+        Some(Command::Scan { csv: Some(path), .. }) => return scan::scan_csv(path),
+        Some(Command::Scan { git_staged, min_words, .. }) => {
+            let list = get_list(cli.path.as_ref(), cli.list, cli.raw)?;
+            return scan::scan_command(*git_staged, &list, *min_words);
+        }
+        Some(Command::Teach { .. }) | Some(Command::Labels { .. }) | Some(Command::ExternalData { .. })
+            | Some(Command::Wizard { .. }) | Some(Command::Derive { .. })
+            | Some(Command::Lookup { .. }) | Some(Command::FromRolls { .. }) | None => {}
+    }
+
+    let warnings = health::check();
+    for warning in &warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    if cli.strict_entropy && !warnings.is_empty() {
+        bail!("Refusing to generate with an unhealthy random number generator (--strict-entropy)");
+    }
+
+    if !cli.lockout.is_empty() && !cli.info && !cli.check {
+        bail!("--lockout needs --info or --check");
+    }
+    if cli.check {
+        if cli.command.is_some() {
+            bail!("--check cannot be combined with a subcommand");
+        }
+        return lint::check_command(&config.terms, &cli.dictionary, cli.units, &cli.lockout);
+    }
+
+    if cli.screenshare && cli.command.is_some() {
+        bail!("--screenshare only copies to the clipboard and cannot be combined with a subcommand");
+    }
+    if cli.count == 0 {
+        bail!("--count must be at least 1");
+    }
+    let json_reports = cli.info && cli.format == Some(Format::Json);
+    if cli.count > 1 && !json_reports && (cli.screenshare || cli.info || cli.format.is_some()) {
+        bail!("--count works with --print, with the clipboard by choosing one to copy, or with --info --format json");
+    }
+    if cli.count > 1 && !cli.print && !json_reports && cli.pick.is_none() && !std::io::stdin().is_terminal() {
+        bail!("Choosing which of the --count passphrases to copy needs a terminal, or --pick N");
+    }
+    if cli.count > 1 && (cli.to_keyfile.is_some() || cli.init_restic.is_some() || cli.init_borg.is_some()) {
+        bail!("--count cannot be combined with --to-keyfile, --init-restic or --init-borg");
+    }
+    // Before the passphrase is shown, since it would be useless without the key
+    if let (Some(_), Some(out)) = (cli.to_keyfile, &cli.out) {
+        keyfile::check(out)?;
+    }
+    let rolls_file = match &cli.command {
+        Some(Command::FromRolls { file }) => Some(file.clone()),
+        _ => None,
+    };
+    if rolls_file.is_some() && (cli.count > 1 || cli.info || cli.interactive || cli.dice || cli.bip39.is_some()
+        || cli.case_mask.is_some() || cli.checksum || cli.format.is_some() || cli.unique || cli.substitute || cli.separator_set.is_some()) {
+        bail!("from-rolls cannot be combined with --count, --info, --interactive, --dice, --bip39, --case-mask, --checksum, --format, --unique, --substitute or --separator-set");
+    }
+    let deriving = matches!(cli.command, Some(Command::Derive { .. }) | Some(Command::ExternalData { .. }));
+    // Derived passphrases and ones picked by dice repeat by design, so only
+    // the generator's own are fingerprinted
+    let track_duplicates = cli.track_duplicates && !deriving && !cli.dice && rolls_file.is_none();
+    if deriving && (cli.count > 1 || cli.interactive || cli.dice || cli.bip39.is_some()) {
+        bail!("Derived passphrases cannot be combined with --count, --interactive, --dice or --bip39");
+    }
+    if (cli.dice || rolls_file.is_some()) && cli.case == Style::Random && !cli.raw {
+        bail!("Dice rolls cannot choose a random case; pick a fixed one");
+    }
+    if (cli.dice || rolls_file.is_some()) && (cli.forbid_chars.is_some() || !cli.require.is_empty()
+        || cli.must_start_with.is_some() || cli.must_end_with.is_some() || cli.min_chars.is_some() || cli.max_chars.is_some()) {
+        bail!("Dice rolls pick the words themselves, so they cannot be held to --forbid-chars, --require, --must-start-with, --must-end-with, --min-chars or --max-chars");
+    }
+    if cli.mode == Mode::Pronounceable {
+        if source(&cli, &matches, "separator") == "default" { cli.separator = "none".to_string(); }
+        if source(&cli, &matches, "case") == "default" { cli.case = Style::Lower; }
+    }
+    if !cli.template.is_empty() {
+        cli.length = cli.template.len();
+    }
+    if cli.case.joins_words() && source(&cli, &matches, "separator") == "default" {
+        cli.separator = "none".to_string();
+    }
+    // Words are letters, so a required digit or symbol that neither the
+    // separator nor the salt can supply is added to the salt, unless the salt
+    // was chosen explicitly
+    let mut salted_classes = 0;
+    for (class, chars) in [(CharClass::Digit, constraint::DIGITS), (CharClass::Symbol, constraint::SYMBOLS)] {
+        if !cli.require.contains(&class) || cli.separator.chars().any(|c| class.matches(c)) {
+            continue;
+        }
+        salted_classes += 1;
+        if !cli.salt_chars.chars().any(|c| class.matches(c)) && matches.occurrences_of("salt-chars") == 0 {
+            eprintln!("Note: adding {} to the salt characters for --require", chars);
+            cli.salt_chars.push_str(chars);
+        }
+    }
+    if cli.salt_length < salted_classes && matches.occurrences_of("salt-length") == 0 {
+        cli.salt_length = salted_classes;
+    }
+    let policy = Policy { require: cli.require.clone(), min_chars: cli.min_chars, max_chars: cli.max_chars };
+    // A pattern places its own words and characters
+    if let Some(pattern) = &cli.pattern {
+        cli.length = pattern.words();
+        cli.salt_length = 0;
+    }
+
+    let wait = if cli.screenshare { SCREENSHARE_WAIT } else { cli.wait };
+    let separator = if cli.separator == "none" { String::new() } else { cli.separator.clone() };
+    let salt_length = cli.salt_length;
     let salt_chars = cli.salt_chars.clone();
-    let case = cli.case.clone();
-    let raw = cli.raw.clone();
+    let raw = cli.raw;
+    let case = match (&cli.case_mask, raw) {
+        (_, true) => Case::Style(Style::None, cli.case_language),
+        (Some(mask), false) => Case::Mask(mask.clone(), cli.case_prob, cli.case_language),
+        (None, false) => Case::Style(cli.case, cli.case_language),
+    };
+
+    if cli.debug > 0 {
+        eprintln!("{:?}", cli.clone());
+        if let Some(compat) = cli.compat {
+            eprintln!("Flags translated from {:?} conventions", compat);
+        }
+    };
 
-    if cli.debug > 0 { eprintln!("{:?}", cli.clone()) };
+    if let (Some(min), Some(max)) = (cli.min_word_len, cli.max_word_len) {
+        if min > max {
+            bail!("--min-word-len {} is more than --max-word-len {}", min, max);
+        }
+    }
+    let (list_id, word_list) = match cli.mode {
+        Mode::Words => (cli.path.clone().unwrap_or_else(|| cli.list.name().to_string()),
+            get_list(cli.path.as_ref(), cli.list, raw)?),
+        // Each syllable is a "word", so generation and entropy work as for a list
+        Mode::Pronounceable => ("syllables".to_string(), wordlist::syllables()),
+    };
+    // Before filtering, so indices match the list as written
+    if let Some(Command::Lookup { word }) = &cli.command {
+        let text = match &cli.path {
+            Some(path) => std::fs::read_to_string(path)?,
+            None => cli.list.text().to_string(),
+        };
+        return lookup(word, &word_list, raw, dice::Numbered::parse(&text, raw).ok());
+    }
 
-    let word_list_result = get_list(cli.path.as_ref(), raw);
+    let forbidden = cli.forbid_chars.as_deref().unwrap_or("");
+    if cli.checksum && forbidden.contains(checksum::SEPARATOR) {
+        bail!("The checksum separator '{}' is forbidden", checksum::SEPARATOR);
+    }
+    if salt_length > 0 && salt_chars.is_empty() {
+        bail!("--salt-chars is empty, so there is nothing to draw {} characters of salt from; give some or pass --salt-length 0",
+            salt_length);
+    }
+    if cli.truncate == Some(0) {
+        bail!("--truncate must keep at least one character");
+    }
+    // A check segment decides how the passphrase ends, leaving the words
+    // nothing to meet there
+    let end = match cli.must_end_with {
+        Some(CharClass::Alnum) if cli.checksum => None,
+        Some(class) if cli.checksum => {
+            bail!("--checksum ends the token with its check segment, which --must-end-with {} cannot be held to",
+                arg_name(&class).unwrap_or(""))
+        }
+        end => end,
+    };
 
-    let word_list = word_list_result?;
+    let mut builder = PassphraseBuilder::new(&list_id, word_list.clone())
+        .length(cli.length)
+        .bits(cli.bits)
+        .separator(&separator)
+        .salt_length(salt_length)
+        .salt_chars(&salt_chars)
+        .case(case.clone())
+        .unique(cli.unique)
+        .forbid(forbidden)
+        .prefix(cli.prefix.as_deref().unwrap_or(""))
+        .must_start_with(cli.must_start_with)
+        .must_end_with(end)
+        .policy(policy);
+    if cli.mode == Mode::Words {
+        builder = builder.word_len(cli.min_word_len, cli.max_word_len);
+    }
+    if let Some(target) = cli.balance_length {
+        builder = builder.balance(target, cli.balance_tolerance);
+    }
+    // A template draws each word from its slot's list
+    if !cli.template.is_empty() {
+        let mut named: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (name, path) in &cli.slot_list {
+            eprintln!("Reading {} words from {}...", name, path);
+            named.insert(name, wordlist::parse(&std::fs::read_to_string(path)?, raw, None, None));
+        }
+        let mut slots = Vec::new();
+        for name in &cli.template {
+            let list = match (named.get(name.as_str()), name.as_str()) {
+                (Some(list), _) => list.clone(),
+                (None, "word") => word_list.clone(),
+                (None, _) => bail!("No list for the {} slot. Give one with --slot-list {}=FILE", name, name),
+            };
+            slots.push((name.clone(), list));
+        }
+        builder = builder.slots(slots);
+    }
+    if let Some(pattern) = &cli.pattern {
+        builder = builder.pattern(pattern.clone());
+    }
+    // Settings that cannot produce enough classes are caught by the attempt
+    // limit
+    if cli.preset == Some(Preset::Ad) || cli.username.is_some() {
+        builder = builder.windows_complex(cli.username.as_deref());
+    }
+    // Derived passphrases depend on the order the steps draw in
+    builder = builder.transforms(Vec::new());
+    if let Some(chars) = cli.truncate {
+        builder = builder.transform(Truncate(chars));
+    }
+    if let Some(set) = &cli.separator_set {
+        builder = builder.random_separators(set);
+    }
+    builder = builder.standard();
+    if cli.substitute {
+        builder = builder.substitute(cli.substitutions.clone(), cli.substitute_prob);
+    }
+    if cli.checksum {
+        builder = builder.transform(checksum::Checksum);
+    }
+    let scheme = builder.scheme().map_err(|err| explain(&cli, err))?;
+    let (length, separator) = (scheme.length(), scheme.separator().to_string());
+    cli.length = length;
+    if cli.separator_set.is_some() {
+        cli.separator = separator.clone();
+    }
+    let word_list = scheme.words();
 
     if cli.debug > 1 {
-        for i in 0..3 {
-            eprintln!("{}", word_list[i])
+        for word in word_list.iter().take(3) {
+            eprintln!("{}", word)
+        }
+    }
+
+    // Derived passphrases draw from a generator seeded by the master secret
+    // and label, attempts included, so they come out the same every time
+    let derived = match &cli.command {
+        Some(Command::Derive { label, fido2, credential }) => {
+            let secret = derive::master_secret()?;
+            let bound = match (fido2, credential) {
+                (Some(device), Some(credential)) => Some(fido2::hmac_secret(device, credential, label)?),
+                _ => None,
+            };
+            Some(RefCell::new(derive::rng(&secret, label, bound.as_deref())?))
+        }
+        Some(Command::ExternalData { label: Some(label) }) => {
+            Some(RefCell::new(derive::rng(&derive::env_secret()?, label, None)?))
         }
+        Some(Command::ExternalData { label: None }) => bail!("external-data reads its label from the query on stdin"),
+        _ => None,
+    };
+    let generate = || -> Result<Vec<Part>> {
+        Ok(match &derived {
+            Some(rng) => scheme.generate_with(&mut *rng.borrow_mut())?,
+            None => scheme.generate_with(&mut rand::thread_rng())?,
+        })
+    };
+
+    let bits = scheme.bits();
+    let (entropy, equivalent) = (bits.total(), bits.equivalent_ascii_characters());
+
+    if let Some(Command::Labels { count, label_prefix, start, csv }) = &cli.command {
+        return labels::batch(&generate, &labels::Batch {
+            count: *count,
+            prefix: label_prefix,
+            start: *start,
+            entropy,
+        }, *csv);
+    }
+
+    if let Some(Command::Teach { students }) = cli.command {
+        return teach::handout(&generate, &teach::Lesson {
+            students,
+            list_len: word_list.len(),
+            length,
+            salt_length,
+            salt_chars: scheme.salt_chars().chars().count(),
+            entropy,
+        });
+    }
+
+    // Pattern analysis of samples knows the word list they come from
+    let sample_checker = || Checker::new(word_list.iter().map(|word| word.to_lowercase()).collect());
+    if cli.format == Some(Format::Badge) {
+        println!("{}", format::badge(entropy));
+        return Ok(());
+    }
+
+    if cli.info && cli.format == Some(Format::Json) {
+        let checker = sample_checker();
+        let combinations = entropy.exp2();
+        for _ in 0..cli.count {
+            let real_sample = generate()?;
+            let sample_phrase = if cli.show_sample {
+                real_sample.clone()
+            } else {
+                placeholder(&real_sample, cli.hide_structure)
+            };
+            let report = report::Report {
+                sample: join_parts(&sample_phrase),
+                sample_is_real: cli.show_sample,
+                word_indices: if cli.show_indices {
+                    Some(sample_phrase.iter()
+                        .filter_map(|part| match part {
+                            Part::Word(_, index) => Some(*index),
+                            _ => None,
+                        })
+                        .collect())
+                } else {
+                    None
+                },
+                boundaries: if cli.hide_structure { None } else { Some(report::boundaries(&sample_phrase)) },
+                policy_compliant: scheme.complies(&join_parts(&real_sample)),
+                entropy: report::Entropy {
+                    known_parameters: entropy,
+                    unknown_parameters: brute_force_entropy(&join_parts(&real_sample)),
+                    list_size: word_list.len(),
+                    words: bits.words,
+                    salt_position: bits.salt_position,
+                    salt_characters: bits.salt_characters,
+                    separators: bits.separators,
+                    case: bits.case,
+                    substitutions: bits.substitutions,
+                    constraints: bits.constraints,
+                    equivalent_ascii_characters: equivalent,
+                    combinations,
+                    guesses_per_second: OFFLINE_GUESSES_PER_SEC,
+                    average_seconds_to_guess: combinations / 2.0 / OFFLINE_GUESSES_PER_SEC,
+                },
+                estimate: report::Estimate::new(&checker.assess(&join_parts(&real_sample)), &cli.lockout),
+                configuration: configuration(&cli, &matches),
+                warnings: warnings.clone(),
+            };
+            // Several reports are written one per line, as JSON Lines
+            if cli.count > 1 {
+                println!("{}", serde_json::to_string(&report)?);
+            } else {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+        }
+        return Ok(());
     }
 
     if cli.info {
-        let sample_phrase = build_passphrase(
-            &word_list, 
-            length, 
-            &separator, 
-            salt_length, 
-            &salt_chars, 
-            if raw { 0 } else { case });
-
-        println!("DO NOT USE THIS PASSPHRASE. Most shells log their history in an unencrypted file. Instead run this program in the standard mode to copy a passphrase directly to your clipboard.");
+        let real_sample = generate()?;
+        let sample_phrase = if cli.show_sample {
+            real_sample.clone()
+        } else {
+            placeholder(&real_sample, cli.hide_structure)
+        };
+
+        if cli.show_sample {
+            println!("DO NOT USE THIS PASSPHRASE. Most shells log their history in an unencrypted file. Instead run this program in the standard mode to copy a passphrase directly to your clipboard.");
+        } else if cli.hide_structure {
+            println!("The sample is hidden under --hide-structure. Pass --show-sample to see a real one.");
+        } else {
+            println!("The sample below is made of placeholders showing the shape of a passphrase. Pass --show-sample to see a real one.");
+        }
         println!();
-        println!("Sample: {}", sample_phrase);
-        let (entropy, equivalent) = entropy(word_list.len(), length, cli.salt_length, &salt_chars);
-        println!("Entropy: {:.2}", entropy);
+        let color = cli.color.enabled();
+        if cli.hide_structure {
+            println!("Sample: {}", join_parts(&sample_phrase));
+        } else {
+            println!("Sample: {}", color::paint_parts(&sample_phrase, cli.boundaries, color));
+        }
+        if cli.display != Display::Plain {
+            for line in display::render(&join_parts(&sample_phrase), cli.display, cli.braille_width) {
+                println!("        {}", line);
+            }
+        }
+        println!("        {}", color::strength_bar(entropy, color));
+        if cli.salt_position_report {
+            println!("Salt: {}", salt_position(&sample_phrase));
+        }
+        if cli.show_indices {
+            for line in word_indices(&sample_phrase, word_list.len()) {
+                println!("{}", line);
+            }
+        }
+        if cli.entropy_model != EntropyModel::UnknownParameters {
+            println!("Word list: {} words, {:.2} bits each", word_list.len(), (word_list.len() as f64).log2());
+            println!("Entropy (attacker knows word list, length and salt scheme): {:.2}", entropy);
+        }
+        if cli.entropy_model != EntropyModel::KnownParameters {
+            println!("Entropy (attacker brute forces this sample's characters): {:.2}",
+                brute_force_entropy(&join_parts(&real_sample)));
+        }
         println!("This is equivalent to a {:.2}-character password of random ASCII characters", equivalent);
+        let locale = Locale::from_env();
+        let combinations = entropy.exp2();
+        println!("{}: {}", locale.possible_passphrases, locale.number(combinations, cli.units));
+        println!("{} ({} {}): {}", locale.average_time,
+            locale.number(OFFLINE_GUESSES_PER_SEC, cli.units), locale.guesses_per_second,
+            locale.duration(combinations / 2.0 / OFFLINE_GUESSES_PER_SEC, cli.units));
+        lint::print_estimate(&sample_checker().assess(&join_parts(&real_sample)), locale, cli.units, cli.show_sample,
+            &cli.lockout);
+        if cli.visual {
+            println!();
+            for line in color::comparison(entropy, color) {
+                println!("{}", line);
+            }
+        }
     } else {
-        let mut ctx: ClipboardContext = ClipboardProvider::new().unwrap();
-        if let Err(err_) = ctx.set_contents(build_passphrase(
-            &word_list, 
-            length, 
-            &separator, 
-            salt_length, 
-            &salt_chars, 
-            if raw { 0 } else { case })) {
-            eprintln!("Could not set clipboard contents: {}", err_);
+        let target = format::Target {
+            host: cli.host.as_deref(),
+            login: cli.login.as_deref(),
+            port: cli.port,
+            database: cli.database.as_deref(),
+            key: cli.key.as_deref(),
+        };
+        if cli.dry_run {
+            return dry_run(&cli, &target, &generate);
+        }
+
+        if let Some(repo) = &cli.init_restic {
+            confirm::confirm_sink(&format!("a new restic repository at {}", repo), cli.yes)?;
+        }
+        if let Some(repo) = &cli.init_borg {
+            confirm::confirm_sink(&format!("a new borg repository at {}", repo), cli.yes)?;
+        }
+        if let (Some(Format::GithubSecret), Some(key)) = (cli.format, &cli.key) {
+            confirm::confirm_sink(
+                &format!("the GitHub Actions secret {} of the repository gh resolves here", key), cli.yes)?;
+        }
+
+        // Cleared once the API has failed, so an offline run asks only once
+        let hibp_reachable = Cell::new(true);
+        let breached = |phrase: &str| -> Result<bool> {
+            if !cli.hibp || !hibp_reachable.get() {
+                return Ok(false);
+            }
+            match hibp::pwned(phrase) {
+                Err(err) if !cli.hibp_required => {
+                    eprintln!("Warning: {:#}. Skipping the breach check.", err);
+                    hibp_reachable.set(false);
+                    Ok(false)
+                }
+                result => result,
+            }
+        };
+        let fresh = || -> Result<String> {
+            for _ in 0..MAX_HIBP_ATTEMPTS {
+                let phrase = join_parts(&generate()?);
+                if !breached(&phrase)? {
+                    return Ok(phrase);
+                }
+                // Deriving another would make the passphrase depend on
+                // whether the API could be reached
+                if derived.is_some() {
+                    bail!("The derived passphrase appears in known breaches. Derive one for another label.");
+                }
+                eprintln!("A generated passphrase appears in known breaches, generating another.");
+            }
+            bail!("Every one of {} passphrases appears in known breaches. Try a longer passphrase.", MAX_HIBP_ATTEMPTS)
         };
 
-        if wait != 0 {
-            std::thread::sleep(std::time::Duration::from_secs(wait));
-            if let Err(err_) = ctx.set_contents(String::new()) {
-                eprintln!("Could not clear clipboard contents: {}", err_);
+        let mut candidates = Vec::new();
+        if cli.count > 1 {
+            for _ in 0..cli.count {
+                let phrase = fresh()?;
+                if track_duplicates && fingerprint::check_and_record(&phrase)? {
+                    bail!("A passphrase repeated one generated before. The random number generator may be broken or this machine may share a cloned seed.");
+                }
+                candidates.push(phrase);
+            }
+            let shown: Vec<String> = candidates.iter()
+                .map(|phrase| cli.shell_quote.map_or(phrase.clone(), |shell| shell.quote(phrase)))
+                .collect();
+            // Piped, one per line for the next program; to a terminal,
+            // numbered to choose from
+            let piped = cli.print && !std::io::stdout().is_terminal();
+            if piped && cli.layout != pick::Layout::Columns {
+                for phrase in &shown {
+                    println!("{}", phrase);
+                }
+                return Ok(());
+            }
+            if cli.pick.is_none() {
+                let width = if cli.layout == pick::Layout::Lines { 0 } else { pick::terminal_width() };
+                for line in pick::columns(&shown, width) {
+                    println!("{}", line);
+                }
+            }
+            if cli.print {
+                return Ok(());
+            }
+        }
+
+        let phrase = if let Some(words) = cli.bip39 {
+            bip39::mnemonic(words)
+        } else if cli.dice || rolls_file.is_some() {
+            let text = match &cli.path {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => cli.list.text().to_string(),
+            };
+            let list = dice::Numbered::parse(&text, cli.raw)?;
+            if salt_length > 0 && matches.occurrences_of("salt-length") > 0 {
+                eprintln!("Note: dice rolls add no salt, since the salt would come from the random number generator");
+            }
+            let rolls = match &rolls_file {
+                Some(file) => dice::read_rolls_file(file)?,
+                None => dice::read_rolls(list.dice(), length)?,
+            };
+            // A file of rolls sets the length unless one was asked for
+            if rolls.len() != length && (rolls_file.is_none() || source(&cli, &matches, "length") != "default") {
+                bail!("{} rolls were given, but --length asks for {} words", rolls.len(), length);
+            }
+            format!("{}{}", cli.prefix.as_deref().unwrap_or(""), list.phrase(&rolls, &case, &separator)?)
+        } else if cli.interactive {
+            let settings = tui::Settings { length, separator: separator.clone() };
+            let generate = |settings: &tui::Settings| -> Result<String> {
+                let scheme = builder.clone().bits(None).length(settings.length).separator(&settings.separator).scheme()?;
+                Ok(join_parts(&scheme.generate_with(&mut rand::thread_rng())?))
+            };
+            let scheme_bits = |length: usize| builder.clone().bits(None).length(length).entropy().unwrap_or(0.0);
+            match tui::run(&generate, &scheme_bits, settings)? {
+                Some(phrase) => phrase,
+                None => return Ok(()),
+            }
+        } else if cli.count > 1 {
+            candidates.swap_remove(pick::choose(cli.count, cli.pick)?)
+        } else {
+            fresh()?
+        };
+        // Dice rolls and a choice made in the terminal cannot be redone here
+        if (cli.dice || rolls_file.is_some() || cli.interactive) && breached(&phrase)? {
+            bail!("This passphrase appears in known breaches. Choose another.");
+        }
+        if track_duplicates && fingerprint::check_and_record(&phrase)? {
+            eprintln!("WARNING: this exact passphrase has been generated before. The random number generator may be broken or this machine may share a cloned seed. Do not use it.");
+        }
+
+        if let Some(Command::ExternalData { .. }) = cli.command {
+            return external::respond(&phrase);
+        }
+
+        if let Some(format) = cli.format {
+            match format {
+                Format::GithubSecret => format::github_secret(&phrase, &target)?,
+                _ => println!("{}", format::render(format, &phrase, &target)?),
+            }
+            return Ok(());
+        }
+
+        // What reaches the user; sinks below still get the bare passphrase
+        let shown = cli.shell_quote.map_or(phrase.clone(), |shell| shell.quote(&phrase));
+        // A configured maximum also rules out never clearing
+        let max_wait = cli.max_wait.unwrap_or(MAX_WAIT);
+        let wait = if wait > max_wait || (wait.is_zero() && cli.max_wait.is_some()) {
+            eprintln!("Warning: --wait is capped at {}, clearing after that instead",
+                humantime::format_duration(max_wait));
+            max_wait
+        } else {
+            wait
+        };
+        let mut ctx: Option<Clipboard> = None;
+        let mut previous: Option<String> = None;
+        // None when printing, whether asked for or as the fallback
+        let clipboard = open_clipboard(&cli)?;
+        let delivered = if let Some(fd) = cli.fd {
+            fd::write(fd, &phrase)?;
+            true
+        } else if let Some(mut clipboard) = clipboard {
+            if let Ok(current) = clipboard.get_contents() {
+                cliplint::confirm_overwrite(&current, cli.force)?;
+                previous = Some(current).filter(|_| !cli.no_restore);
+            }
+            let copied = if cli.detach {
+                detach::spawn(&detach::Handoff {
+                    contents: shown.clone(),
+                    previous: previous.take(),
+                    wait,
+                    osc52: cli.osc52,
+                    clip_exe: matches!(clipboard, Clipboard::ClipExe),
+                    bell: cli.bell,
+                    title_countdown: cli.title_countdown,
+                })
+            } else {
+                clipboard.set_contents(shown.clone())
+            };
+            match copied {
+                Ok(()) => {
+                    if cli.bell {
+                        clipboard::bell();
+                    }
+                    if !cli.detach {
+                        ctx = Some(clipboard);
+                    }
+                    true
+                }
+                Err(err_) => {
+                    eprintln!("Could not set clipboard contents: {}", err_);
+                    false
+                }
+            }
+        } else {
+            if cli.screenshare {
+                bail!("--screenshare never prints the passphrase, and there is no clipboard to copy it to");
+            }
+            println!("{}", shown);
+            true
+        };
+        // Only derive once the passphrase has reached the user, or the key
+        // could not be recovered from it
+        match (delivered, cli.to_keyfile, &cli.out) {
+            (true, Some(kdf), Some(out)) => keyfile::write(kdf, &phrase, cli.bytes, out)?,
+            (false, Some(_), Some(out)) => {
+                bail!("Not writing a key to {}, since the passphrase could not be delivered", out)
+            }
+            _ => {}
+        }
+
+        // Likewise a repository encrypted with a passphrase nobody saw could
+        // never be opened
+        let backup = match (&cli.init_restic, &cli.init_borg) {
+            (Some(repo), _) => Some((backup::Tool::Restic, repo)),
+            (None, Some(repo)) => Some((backup::Tool::Borg, repo)),
+            (None, None) => None,
+        };
+        match (delivered, backup) {
+            (true, Some((tool, repo))) => backup::init(tool, repo, &phrase)?,
+            (false, Some((tool, repo))) => {
+                bail!("Not initializing {:?} repository {}, since the passphrase could not be delivered", tool, repo)
+            }
+            (_, None) => {}
+        }
+
+        let mut ctx = match ctx {
+            Some(ctx) => ctx,
+            None => return Ok(()),
+        };
+        if cli.screenshare {
+            eprintln!("Copied. The clipboard clears in {}.", humantime::format_duration(wait));
+        }
+        if wait.is_zero() {
+            eprintln!("WARNING: --wait 0 leaves the passphrase in the clipboard until something else replaces it. Clear it yourself once it has been pasted.");
+        } else {
+            countdown::wait(wait, cli.title_countdown);
+            ctx.release(&shown, previous);
+            if cli.bell {
+                clipboard::bell();
             }
         }
     }
@@ -0,0 +1,132 @@
+//! Passphrases spelled out one position at a time, as in `Wd-Ww-###`. Each
+//! token is a word, a random character from a class, or literal text, so
+//! the structure is fixed and the entropy is the sum of the random tokens.
+
+use rand::Rng;
+
+use crate::case::{Case, Language, Style};
+use crate::Part;
+
+/// One position of a pattern
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    /// W: a word cased as the rest of the settings say
+    Word,
+    /// w: a lowercase word
+    LowerWord,
+    /// d: a digit
+    Digit,
+    /// s: a symbol
+    Symbol,
+    /// #: a salt character
+    Salt,
+    Literal(String),
+}
+
+/// The characters the random character tokens draw from
+#[derive(Clone, Debug)]
+pub struct Alphabets {
+    pub digits: String,
+    pub symbols: String,
+    pub salt: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern(pub Vec<Token>);
+
+/// Parses a pattern of W and w for words, d for digits, s for symbols and
+/// # for salt characters. Anything else is literal, and a backslash makes
+/// the next character literal.
+pub fn parse(s: &str) -> Result<Pattern, String> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            'W' => Token::Word,
+            'w' => Token::LowerWord,
+            'd' => Token::Digit,
+            's' => Token::Symbol,
+            '#' => Token::Salt,
+            '\\' => match chars.next() {
+                Some(escaped) => Token::Literal(escaped.to_string()),
+                None => return Err("a trailing \\ escapes nothing".to_string()),
+            },
+            _ => Token::Literal(c.to_string()),
+        };
+        // Runs of literal text are one part
+        match (tokens.last_mut(), &token) {
+            (Some(Token::Literal(text)), Token::Literal(next)) => text.push_str(next),
+            _ => tokens.push(token),
+        }
+    }
+    if !tokens.iter().any(|token| !matches!(token, Token::Literal(_))) {
+        return Err("the pattern has nothing random in it".to_string());
+    }
+    Ok(Pattern(tokens))
+}
+
+impl Pattern {
+    fn count(&self, f: impl Fn(&Token) -> bool) -> usize {
+        self.0.iter().filter(|token| f(token)).count()
+    }
+
+    /// Number of words, of either case
+    pub fn words(&self) -> usize {
+        self.count(|token| matches!(token, Token::Word | Token::LowerWord))
+    }
+
+    /// Number of lowercase words, which `case` does not apply to
+    pub fn lower_words(&self) -> usize {
+        self.count(|token| *token == Token::LowerWord)
+    }
+
+    /// The first character class token with nothing to draw from, if any
+    pub fn empty_class(&self, alphabets: &Alphabets) -> Option<&'static str> {
+        [
+            (Token::Digit, &alphabets.digits, "digit"),
+            (Token::Symbol, &alphabets.symbols, "symbol"),
+            (Token::Salt, &alphabets.salt, "salt"),
+        ]
+        .iter()
+        .find(|(token, chars, _)| self.0.contains(token) && chars.is_empty())
+        .map(|(_, _, name)| *name)
+    }
+
+    /// Bits of the character tokens. Words are counted as for any other
+    /// passphrase.
+    pub fn char_bits(&self, alphabets: &Alphabets) -> f64 {
+        let bits = |chars: &str| (chars.chars().count() as f64).log2();
+        self.count(|token| *token == Token::Digit) as f64 * bits(&alphabets.digits)
+            + self.count(|token| *token == Token::Symbol) as f64 * bits(&alphabets.symbols)
+            + self.count(|token| *token == Token::Salt) as f64 * bits(&alphabets.salt)
+    }
+
+    /// Fills in the pattern with words from `list`, casing `W` words with
+    /// `case` and `w` words in lowercase
+    pub fn build<R: Rng>(&self, rng: &mut R, list: &[String], case: &Case, language: Language,
+        alphabets: &Alphabets) -> Vec<Part> {
+        let lower = Case::Style(Style::Lower, language);
+        let pick = |rng: &mut R, chars: &str| {
+            let chars: Vec<char> = chars.chars().collect();
+            Part::Salt(chars[rng.gen_range(0..chars.len())].to_string())
+        };
+
+        let mut words = 0;
+        self.0.iter().map(|token| match token {
+            Token::Word | Token::LowerWord => {
+                let index = rng.gen_range(0..list.len());
+                let mut word = list[index].clone();
+                match token {
+                    Token::Word => case.for_position(words).apply(&mut word, rng),
+                    _ => lower.apply(&mut word, rng),
+                }
+                words += 1;
+                Part::Word(word, index)
+            }
+            Token::Digit => pick(rng, &alphabets.digits),
+            Token::Symbol => pick(rng, &alphabets.symbols),
+            Token::Salt => pick(rng, &alphabets.salt),
+            Token::Literal(text) => Part::Literal(text.clone()),
+        }).collect()
+    }
+}
@@ -0,0 +1,73 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{bail, Result};
+use clap::ArgEnum;
+
+/// Width assumed when the terminal's cannot be found
+const DEFAULT_WIDTH: usize = 80;
+/// Spaces between columns
+const GAP: usize = 2;
+
+/// How --count passphrases are printed
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    /// Numbered columns on a terminal, one per line into a pipe
+    Auto,
+    /// Numbered columns, even into a pipe
+    Columns,
+    /// One per line, numbered on a terminal
+    Lines,
+}
+
+/// Width of the terminal on stdout
+pub fn terminal_width() -> usize {
+    match crossterm::terminal::size() {
+        Ok((columns, _)) if columns > 0 => columns as usize,
+        _ => DEFAULT_WIDTH,
+    }
+}
+
+/// Lays `phrases` out numbered from 1 in as many aligned columns as fit in
+/// `width`, row by row as pwgen does
+pub fn columns(phrases: &[String], width: usize) -> Vec<String> {
+    let number_width = phrases.len().to_string().len();
+    let phrase_width = phrases.iter().map(|phrase| phrase.chars().count()).max().unwrap_or(0);
+    let cell = number_width + 2 + phrase_width;
+    let per_row = ((width + GAP) / (cell + GAP)).max(1);
+
+    phrases.chunks(per_row)
+        .enumerate()
+        .map(|(row, chunk)| {
+            chunk.iter()
+                .enumerate()
+                .map(|(i, phrase)| format!("{:>nw$}) {:<pw$}", row * per_row + i + 1, phrase,
+                    nw = number_width, pw = phrase_width))
+                .collect::<Vec<_>>()
+                .join(&" ".repeat(GAP))
+                .trim_end()
+                .to_string()
+        })
+        .collect()
+}
+
+/// The 0-based index of the candidate to copy: `pick` if given, or else a
+/// number asked for on the terminal, which stdin must be
+pub fn choose(count: usize, pick: Option<usize>) -> Result<usize> {
+    let number = match pick {
+        Some(number) => number,
+        None => {
+            eprint!("Copy which passphrase? [1-{}] ", count);
+            std::io::stderr().flush()?;
+            let mut answer = String::new();
+            std::io::stdin().lock().read_line(&mut answer)?;
+            match answer.trim().parse() {
+                Ok(number) => number,
+                Err(_) => bail!("{} is not a number; nothing was copied", answer.trim()),
+            }
+        }
+    };
+    if number == 0 || number > count {
+        bail!("There is no passphrase {}; choose from 1 to {}", number, count);
+    }
+    Ok(number - 1)
+}
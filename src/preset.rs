@@ -0,0 +1,92 @@
+use clap::{ArgEnum, ArgMatches};
+
+use passphrs::case::Style;
+use passphrs::wordlist::List;
+
+use crate::Cli;
+
+/// URL and filename safe base64 alphabet (RFC 4648 section 5)
+pub const BASE64URL: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+const DIGITS: &str = "0123456789";
+
+/// Named bundles of settings for common kinds of secret
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Preset {
+    /// Four lowercase words joined by '-' with an 8-character base64url salt
+    Token,
+    /// 32 random base64url characters
+    RandomToken,
+    /// Eight lowercase words joined by '-', for backup repository encryption
+    Backup,
+    /// Three short lowercase words and two digits, easy to type on a keypad
+    /// or TV remote, for default device credentials
+    Device,
+    /// Five capitalized words joined by '-' with a digit, which always meets
+    /// the default Active Directory complexity rules
+    Ad,
+    /// Eight digits, a PIN accepted by PIV and OpenPGP cards alike
+    PivPin,
+    /// Eight digits, the longest PIV PUK and an OpenPGP admin PIN
+    Puk,
+}
+
+impl Preset {
+    /// Ids of the settings the preset changes
+    pub fn settings(self) -> &'static [&'static str] {
+        match self {
+            Preset::Token => &["length", "separator", "case", "salt-length", "salt-chars"],
+            Preset::Backup => &["length", "separator", "case", "salt-length"],
+            Preset::Ad => &["length", "separator", "case", "salt-length", "salt-chars"],
+            Preset::Device => &["length", "separator", "case", "salt-length", "salt-chars", "list"],
+            Preset::RandomToken | Preset::PivPin | Preset::Puk => &["length", "salt-length", "salt-chars"],
+        }
+    }
+
+    /// Applies the preset to every setting that was not given explicitly
+    pub fn apply(self, cli: &mut Cli, matches: &ArgMatches) {
+        let unset = |id: &str| matches.occurrences_of(id) == 0;
+        match self {
+            Preset::Token => {
+                if unset("length") { cli.length = 4; }
+                if unset("separator") { cli.separator = "-".to_string(); }
+                if unset("case") { cli.case = Style::Lower; }
+                if unset("salt-length") { cli.salt_length = 8; }
+                if unset("salt-chars") { cli.salt_chars = BASE64URL.to_string(); }
+            }
+            Preset::Backup => {
+                if unset("length") { cli.length = 8; }
+                if unset("separator") { cli.separator = "-".to_string(); }
+                if unset("case") { cli.case = Style::Lower; }
+                if unset("salt-length") { cli.salt_length = 0; }
+            }
+            Preset::RandomToken => {
+                if unset("length") { cli.length = 0; }
+                if unset("salt-length") { cli.salt_length = 32; }
+                if unset("salt-chars") { cli.salt_chars = BASE64URL.to_string(); }
+            }
+            Preset::Ad => {
+                if unset("length") { cli.length = 5; }
+                if unset("separator") { cli.separator = "-".to_string(); }
+                if unset("case") { cli.case = Style::Capitalized; }
+                if unset("salt-length") { cli.salt_length = 1; }
+                if unset("salt-chars") { cli.salt_chars = DIGITS.to_string(); }
+            }
+            Preset::Device => {
+                if unset("length") { cli.length = 3; }
+                if unset("separator") { cli.separator = "-".to_string(); }
+                if unset("case") { cli.case = Style::Lower; }
+                if unset("salt-length") { cli.salt_length = 2; }
+                if unset("salt-chars") { cli.salt_chars = DIGITS.to_string(); }
+                if unset("list") { cli.list = List::EffShort; }
+            }
+            // PIV allows 6-8 byte PINs and PUKs, and some middleware only
+            // accepts digits
+            Preset::PivPin | Preset::Puk => {
+                if unset("length") { cli.length = 0; }
+                if unset("salt-length") { cli.salt_length = 8; }
+                if unset("salt-chars") { cli.salt_chars = DIGITS.to_string(); }
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use passphrs::strength::{Assessment, Lockout, ATTACKS};
+use passphrs::Part;
+
+/// Everything `--info --format json` reports, for dashboards that track
+/// passphrase policy across a fleet
+#[derive(Serialize)]
+pub struct Report {
+    /// The sample, made of placeholders unless `sample_is_real`
+    pub sample: String,
+    pub sample_is_real: bool,
+    /// Word list index of each word, only for a real sample
+    pub word_indices: Option<Vec<usize>>,
+    /// Character offsets in `sample` of the separators between words, empty
+    /// ones included, as --boundaries marks them. Left out under
+    /// --hide-structure.
+    pub boundaries: Option<Vec<usize>>,
+    /// Whether the real sample meets --require, --min-chars, --max-chars,
+    /// --must-start-with, --must-end-with and the Windows complexity rules,
+    /// as every generated passphrase does
+    pub policy_compliant: bool,
+    pub entropy: Entropy,
+    pub estimate: Estimate,
+    pub configuration: BTreeMap<&'static str, Setting>,
+    /// Problems found with the random number generator
+    pub warnings: Vec<String>,
+}
+
+/// Bits contributed by each part of the scheme. `known_parameters` is their
+/// sum.
+#[derive(Serialize)]
+pub struct Entropy {
+    pub known_parameters: f64,
+    pub unknown_parameters: f64,
+    /// Words left in the list after --min-word-len, --max-word-len and
+    /// --forbid-chars, which `words` counts
+    pub list_size: usize,
+    pub words: f64,
+    /// Choices of --separator-set
+    pub separators: f64,
+    pub salt_position: f64,
+    pub salt_characters: f64,
+    pub case: f64,
+    /// Which letters --substitute replaced
+    pub substitutions: f64,
+    /// Negative: the share of passphrases the start/end constraints reject
+    pub constraints: f64,
+    pub equivalent_ascii_characters: f64,
+    pub combinations: f64,
+    pub guesses_per_second: f64,
+    pub average_seconds_to_guess: f64,
+}
+
+/// The sample as an attacker who tries patterns first sees it
+#[derive(Serialize)]
+pub struct Estimate {
+    pub bits: f64,
+    pub guesses: f64,
+    /// Under each attack model, by name
+    pub average_seconds_to_guess: BTreeMap<&'static str, f64>,
+    /// Under each --lockout policy, by its description
+    pub average_seconds_with_lockout: BTreeMap<String, f64>,
+    /// Kinds of weakness found
+    pub patterns: Vec<&'static str>,
+}
+
+impl Estimate {
+    pub fn new(assessment: &Assessment, lockouts: &[Lockout]) -> Estimate {
+        let guesses = assessment.guesses();
+        Estimate {
+            bits: assessment.bits,
+            guesses,
+            average_seconds_to_guess: ATTACKS.iter()
+                .map(|attack| (attack.name, guesses / 2.0 / attack.guesses_per_second))
+                .collect(),
+            average_seconds_with_lockout: lockouts.iter()
+                .map(|lockout| (lockout.to_string(), lockout.average_seconds(guesses)))
+                .collect(),
+            patterns: assessment.findings.iter().map(|finding| finding.weakness.kind()).collect(),
+        }
+    }
+}
+
+/// Character offsets in the joined `parts` at which each separator between
+/// words begins
+pub fn boundaries(parts: &[Part]) -> Vec<usize> {
+    let mut offsets = Vec::new();
+    let mut offset = 0;
+    for part in parts {
+        if let Part::Separator(_) = part {
+            offsets.push(offset);
+        }
+        offset += part.as_str().chars().count();
+    }
+    offsets
+}
+
+/// A resolved setting and where its value came from
+#[derive(Serialize)]
+pub struct Setting {
+    pub value: Value,
+    /// "command line", "preset" or "default"
+    pub source: &'static str,
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    use passphrs::{Part, PassphraseBuilder};
+
+    use super::boundaries;
+
+    #[test]
+    fn boundaries_unseparated() {
+        let phrase = PassphraseBuilder::default()
+            .separator("")
+            .salt_length(0)
+            .build_with(&mut ChaCha20Rng::seed_from_u64(1))
+            .unwrap();
+        let mut expected = Vec::new();
+        let mut end = 0;
+        for part in &phrase.components {
+            if let Part::Word(word, _) = part {
+                end += word.chars().count();
+                expected.push(end);
+            }
+        }
+        expected.pop();
+        assert_eq!(phrase.components.len(), 13);
+        assert_eq!(boundaries(&phrase.components), expected);
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::HashSet;
+use std::io::Read;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// Longest gap between words that can still be a separator plus salt
+const MAX_GAP: usize = 6;
+
+/// Byte ranges of the words in `line`: alphabetic runs, also broken where a
+/// lowercase letter is followed by an uppercase one as in joined phrases
+fn words(line: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    let mut prev_lower = false;
+    for (i, c) in line.char_indices() {
+        if c.is_alphabetic() {
+            if let (Some(s), true) = (start, prev_lower && c.is_uppercase()) {
+                words.push((s, i));
+                start = None;
+            }
+            start.get_or_insert(i);
+            prev_lower = c.is_lowercase();
+        } else {
+            if let Some(s) = start.take() {
+                words.push((s, i));
+            }
+            prev_lower = false;
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, line.len()));
+    }
+    words
+}
+
+/// Runs this long are reported whatever their shape
+const LONG_RUN: usize = 5;
+/// Runs this long are reported when salted, as `--preset device` and
+/// `token` are. Unsalted, they are as likely to be identifiers such as
+/// snake_case_names.
+const SHORT_RUN: usize = 3;
+/// Longest salt that can be stuck to a word
+const MAX_SALT: usize = 32;
+
+/// Pieces of passphrs's own output that carry a passphrase: external-data's
+/// JSON, the label sheet's password lines and its CSV header
+const RECEIPTS: [&str; 3] = ["\"passphrase\":", "| Password: ", "label,passphrase"];
+
+/// A run of list words found in a line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Run {
+    pub words: usize,
+    /// Whether salt was stuck to one of the words
+    pub salted: bool,
+    /// Characters of letters, digits and underscores stuck to one of the
+    /// words, salt or not
+    pub stuck: usize,
+    /// Whether the words were joined by '-'
+    pub dashed: bool,
+}
+
+impl Run {
+    /// Whether the run is reported without an explicit `--min-words`
+    fn shaped(&self) -> bool {
+        // --preset token's base64url salt can be all letters, but not its
+        // length too
+        let token = self.words == 4 && self.dashed && self.stuck == 8;
+        self.words >= LONG_RUN || (self.words >= SHORT_RUN && self.salted) || token
+    }
+}
+
+/// Splits the text between two list words into the salt stuck to the
+/// first and the separator. Digits are salt wherever they are.
+fn split_gap(gap: &str, separator: Option<&str>) -> (String, String) {
+    let gap: String = gap.chars().filter(|c| !c.is_ascii_digit()).collect();
+    let at = match separator {
+        Some(separator) if !separator.is_empty() && gap.ends_with(separator) => gap.len() - separator.len(),
+        Some(_) => gap.len(),
+        // Unknown yet, anything from the first space on, or else the last
+        // character, is the separator
+        None => gap.find(char::is_whitespace)
+            .or_else(|| gap.char_indices().last().map(|(i, _)| i))
+            .unwrap_or(0),
+    };
+    (gap[..at].to_string(), gap[at..].to_string())
+}
+
+/// Whether `text` has salt in it: a digit, or a symbol other than the
+/// separator
+fn has_salt(text: &str) -> bool {
+    text.chars().any(|c| c.is_ascii_digit() || c.is_ascii_punctuation())
+}
+
+/// Length of the letters, digits and underscores `text` starts with
+fn stuck_len(text: &str) -> usize {
+    text.chars().take_while(|c| c.is_ascii_alphanumeric() || *c == '_').count()
+}
+
+/// Looks for consecutive list words joined by a consistent separator,
+/// allowing salt stuck to one of them, and returns the longest run. Letters
+/// stuck to a word are allowed as part of a salt, but only digits and
+/// symbols make the run salted, since identifiers such as userProfileId
+/// have letters stuck to words too.
+pub fn scan_line(line: &str, list: &HashSet<String>) -> Option<Run> {
+    let ranges = words(line);
+    let mut best: Option<Run> = None;
+    let mut run = Run { words: 0, salted: false, stuck: 0, dashed: false };
+    let mut separator: Option<String> = None;
+    let mut prev_end: Option<usize> = None;
+    // Where salt stuck to the last word began, while it is being read
+    let mut salt_start: Option<usize> = None;
+    // Digits straight after the last word, before a break or the end
+    let trailing = |prev_end: Option<usize>| prev_end.is_some_and(|prev| line[prev..].starts_with(|c: char| c.is_ascii_digit()));
+
+    for (k, &(start, end)) in ranges.iter().enumerate() {
+        let word = &line[start..end];
+        let listed = list.contains(&word.to_lowercase());
+        // A list word with lowercase salt stuck on, told apart from a longer
+        // ordinary word by the salt going on past the letters: into a digit,
+        // or into more letters that are not a word, unlike in camelCase
+        let followed = match (line[end..].chars().next(), ranges.get(k + 1)) {
+            (Some(c), _) if c.is_ascii_digit() => true,
+            (Some(_), Some(&(next_start, next_end))) if next_start == end =>
+                !list.contains(&line[next_start..next_end].to_lowercase()),
+            _ => false,
+        };
+        let salt_at = if listed || !followed {
+            None
+        } else {
+            (1..word.len()).rev().find(|&i| word.is_char_boundary(i) && list.contains(&word[..i].to_lowercase()))
+        };
+
+        if !listed && salt_at.is_none() {
+            // Letters of salt stuck to the last word, as base64url salt's
+            // are, with only digits and underscores between them. An
+            // underscore alone does not start a salt, as in snake_case.
+            let stuck = prev_end.is_some_and(|prev| {
+                let gap = &line[prev..start];
+                gap.chars().all(|c| c.is_ascii_digit() || c == '_')
+                    && (salt_start.is_some() || !gap.contains('_') || gap.contains(|c: char| c.is_ascii_digit()))
+            });
+            if let (Some(from), true) = (salt_start.or(prev_end), stuck) {
+                if end - from <= MAX_SALT && (salt_start.is_some() || !run.salted) {
+                    salt_start = Some(from);
+                    run.salted |= has_salt(&line[from..end]);
+                    run.stuck = stuck_len(&line[from..]);
+                    if best.is_some_and(|b| b.words == run.words) {
+                        best = Some(run);
+                    }
+                    prev_end = Some(end);
+                    continue;
+                }
+            }
+            if trailing(prev_end) && best.is_some_and(|b| b.words == run.words) {
+                best = Some(Run { salted: true, ..run });
+            }
+            run = Run { words: 0, salted: false, stuck: 0, dashed: false };
+            separator = None;
+            prev_end = None;
+            salt_start = None;
+            continue;
+        }
+
+        if let Some(prev) = prev_end {
+            let gap = &line[prev..start];
+            let digits = gap.contains(|c: char| c.is_ascii_digit());
+            let fits = |salt: &str, sep: &str| sep.len() <= MAX_GAP && salt.chars().count() <= MAX_SALT;
+            let (salt, sep) = split_gap(gap, separator.as_deref());
+            let salted = !salt.is_empty() || digits;
+            let consistent = separator.as_ref().is_none_or(|s| *s == sep);
+            // Only one word is salted, though digits after letters of salt
+            // are more of the same salt
+            let second_salt = salted && run.salted && salt_start.is_none();
+            if fits(&salt, &sep) && consistent && !second_salt {
+                run.words += 1;
+                run.salted |= salted;
+                run.dashed = sep == "-";
+                separator = Some(sep);
+            } else {
+                // A new run of the last word and this one, with a separator
+                // of their own
+                let (salt, sep) = split_gap(gap, None);
+                if fits(&salt, &sep) {
+                    run = Run { words: 2, salted: !salt.is_empty() || digits, stuck: 0, dashed: sep == "-" };
+                    separator = Some(sep);
+                } else {
+                    run = Run { words: 1, salted: false, stuck: 0, dashed: false };
+                    separator = None;
+                }
+            }
+        } else {
+            run = Run { words: 1, salted: false, stuck: 0, dashed: false };
+        }
+        salt_start = salt_at.filter(|_| !run.salted).map(|i| start + i);
+        if let Some(from) = salt_start {
+            run.stuck = stuck_len(&line[from..]);
+        }
+        prev_end = Some(end);
+        if best.is_none_or(|b| run.words > b.words) {
+            best = Some(run);
+        }
+    }
+    if trailing(prev_end) && best.is_some_and(|b| b.words == run.words) {
+        best = Some(Run { salted: true, ..run });
+    }
+
+    best
+}
+
+/// Which of passphrs's own outputs `line` looks like, if any
+pub fn receipt(line: &str) -> Option<&'static str> {
+    RECEIPTS.iter().copied().find(|marker| line.contains(marker))
+}
+
+/// Lines added in the staged diff, with the file and line number they land on
+fn staged_lines() -> Result<Vec<(String, String)>> {
+    let out = Command::new("git")
+        .args(["diff", "--cached", "--unified=0", "--no-color"])
+        .output()
+        .context("Could not run git")?;
+    if !out.status.success() {
+        bail!("git diff failed: {}", String::from_utf8_lossy(&out.stderr).trim());
+    }
+
+    let mut lines = Vec::new();
+    let mut file = String::new();
+    let mut number = 0;
+    for line in String::from_utf8_lossy(&out.stdout).lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            file = path.trim_start_matches("b/").to_string();
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            // @@ -a,b +c,d @@ puts the first added line at c
+            number = hunk.split_whitespace()
+                .find_map(|part| part.strip_prefix('+'))
+                .and_then(|part| part.split(',').next())
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(0);
+        } else if let Some(added) = line.strip_prefix('+') {
+            lines.push((format!("{}:{}", file, number), added.to_string()));
+            number += 1;
+        }
+    }
+    Ok(lines)
+}
+
+/// Scans staged changes, or stdin, for text shaped like generated passphrases
+/// or passphrs's own output. Runs of at least `min_words` words are reported
+/// whatever their shape; without it, short runs only in the shape of a
+/// preset. Matches are reported by location only, so the secrets are not
+/// echoed.
+pub fn scan_command(git_staged: bool, list: &[String], min_words: Option<usize>) -> Result<()> {
+    let list: HashSet<String> = list.iter().map(|w| w.to_lowercase()).collect();
+    let lines = if git_staged {
+        staged_lines()?
+    } else {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        input.lines()
+            .enumerate()
+            .map(|(i, line)| (format!("stdin:{}", i + 1), line.to_string()))
+            .collect()
+    };
+
+    let mut found = 0;
+    for (location, line) in &lines {
+        if let Some(receipt) = receipt(line) {
+            eprintln!("{}: passphrs output ({}), possibly with a passphrase", location, receipt.trim());
+            found += 1;
+            continue;
+        }
+        let run = match scan_line(line, &list) {
+            Some(run) if min_words.map_or(run.shaped(), |min| run.words >= min) => run,
+            _ => continue,
+        };
+        let salt = if run.salted { " and salt" } else { "" };
+        eprintln!("{}: {} consecutive word list words{}, possibly a passphrase", location, run.words, salt);
+        found += 1;
+    }
+
+    if found > 0 {
+        bail!("Found {} possible passphrase(s). Remove them, or commit with --no-verify if they are not secrets.", found);
+    }
+    Ok(())
+}
+
+/// One credential from a hashed export
+struct Record {
+    id: String,
+    hash: String,
+    words: HashSet<String>,
+}
+
+/// Reports reuse in a hashed credential export. The CSV needs a `hash`
+/// column and may have an `id` column and a `word_hashes` column of
+/// space-separated per-word hashes, which enables near-duplicate detection.
+/// Only hashes are compared, so no plaintext is ever needed.
+pub fn scan_csv(path: &str) -> Result<()> {
+    let mut reader = csv::Reader::from_path(path).with_context(|| format!("Could not read {}", path))?;
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let hash_col = match column("hash") {
+        Some(col) => col,
+        None => bail!("{} has no hash column", path),
+    };
+    let (id_col, words_col) = (column("id"), column("word_hashes"));
+
+    let mut records = Vec::new();
+    for (i, row) in reader.records().enumerate() {
+        let row = row?;
+        records.push(Record {
+            id: id_col.and_then(|col| row.get(col)).map_or_else(|| format!("row {}", i + 1), str::to_string),
+            hash: row.get(hash_col).unwrap_or("").trim().to_lowercase(),
+            words: words_col.and_then(|col| row.get(col))
+                .map(|w| w.split_whitespace().map(str::to_lowercase).collect())
+                .unwrap_or_default(),
+        });
+    }
+
+    let mut findings = 0;
+    let mut by_hash: std::collections::BTreeMap<&str, Vec<&str>> = Default::default();
+    for record in records.iter().filter(|r| !r.hash.is_empty()) {
+        by_hash.entry(&record.hash).or_default().push(&record.id);
+    }
+    for ids in by_hash.values().filter(|ids| ids.len() > 1) {
+        println!("Identical: {}", ids.join(", "));
+        findings += 1;
+    }
+
+    // Near duplicates share at least half the words of the shorter phrase
+    for (i, a) in records.iter().enumerate() {
+        for b in &records[i + 1..] {
+            if a.hash == b.hash || a.words.is_empty() || b.words.is_empty() {
+                continue;
+            }
+            let shared = a.words.intersection(&b.words).count();
+            let shorter = a.words.len().min(b.words.len());
+            if shared >= 2 && shared * 2 >= shorter {
+                println!("Shares {} of {} words: {}, {}", shared, shorter, a.id, b.id);
+                findings += 1;
+            }
+        }
+    }
+
+    if findings > 0 {
+        bail!("Found {} case(s) of passphrase reuse in {}", findings, path);
+    }
+    println!("No reuse found among {} credentials", records.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use passphrs::wordlist::{self, List};
+
+    use super::scan_line;
+
+    fn shaped(line: &str) -> bool {
+        let list: HashSet<String> = wordlist::parse(List::EffLarge.text(), false, None, None).into_iter().collect();
+        scan_line(line, &list).is_some_and(|run| run.shaped())
+    }
+
+    #[test]
+    fn preset_output() {
+        // --preset device, token, backup and ad, and the default
+        assert!(shaped("shore-tiger42-pond"));
+        assert!(shaped("shore-tiger-pond42"));
+        assert!(shaped("tiger-lemonK3b_x9Qa-kiwi-pond"));
+        assert!(shaped("everyone-goldfishdfwpxy9a-triceps-cottage"));
+        assert!(shaped("cornmeal-grunt6_oD1WTq-crease-device"));
+        assert!(shaped("surrender-same-dwindleSkHQ1bf4-enduring"));
+        assert!(shaped("demystifyFWqqwgxz-speech-barcode-grievance"));
+        assert!(shaped("decorated-campus-polygraph-lispjmvnBIyB"));
+        assert!(shaped("rickety-disorder-justifier-submersed-ocelot-java-landslide-tiger"));
+        assert!(shaped("Bulb-Harvest-Pretense-Mold7-Voltage"));
+        assert!(shaped("Conceal Monstrous Whiff Resort\" Outnumber Pointer Exemption"));
+        assert!(shaped("password = \"shore-tiger42-pond\""));
+    }
+
+    #[test]
+    fn identifiers() {
+        assert!(!shaped("let snake_case_variable_name = 1;"));
+        assert!(!shaped("user.profile.avatar.url"));
+        assert!(!shaped("background-color: dark-olive-green;"));
+        assert!(!shaped("<div class=\"dark-olive-green-border\">"));
+        assert!(!shaped("fn fetchUserProfileId() {}"));
+        assert!(!shaped("let active_user_names_list = Vec::new();"));
+        assert!(!shaped("I went to the shop"));
+    }
+}
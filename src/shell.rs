@@ -0,0 +1,35 @@
+use clap::ArgEnum;
+
+/// Shells whose quoting rules --shell-quote knows
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Shell {
+    /// POSIX sh and compatible shells such as bash and zsh
+    Sh,
+    Fish,
+    Powershell,
+}
+
+impl Shell {
+    /// Quotes `s` as a single argument, so separators never split it
+    pub fn quote(self, s: &str) -> String {
+        let body: String = match self {
+            // Nothing is special inside single quotes, so a quote ends the
+            // string, is escaped and the string starts again
+            Shell::Sh => s.replace('\'', "'\\''"),
+            Shell::Fish => s.chars()
+                .flat_map(|c| match c {
+                    '\\' | '\'' => vec!['\\', c],
+                    _ => vec![c],
+                })
+                .collect(),
+            // PowerShell also takes the typographic single quotes as quotes
+            Shell::Powershell => s.chars()
+                .flat_map(|c| match c {
+                    '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' => vec![c, c],
+                    _ => vec![c],
+                })
+                .collect(),
+        };
+        format!("'{}'", body)
+    }
+}
@@ -0,0 +1,893 @@
+use std::collections::HashSet;
+use std::fmt;
+
+/// Fewest words a chosen phrase should have. Chosen words are far from
+/// random, so this is more than a generated passphrase needs.
+pub const MIN_WORDS: usize = 5;
+/// Shortest run of neighbouring keys reported as a keyboard walk
+const MIN_WALK: usize = 4;
+/// Shortest run of evenly spaced letters or digits reported as a sequence
+const MIN_SEQUENCE: usize = 4;
+/// Shortest word reported when written in leetspeak or found among terms
+const MIN_TERM: usize = 4;
+
+/// Phrases so well known that they are among the first an attacker tries,
+/// written in lowercase letters only
+const QUOTES: [&str; 24] = [
+    "correcthorsebatterystaple",
+    "tobeornottobe",
+    "thequickbrownfoxjumpsoverthelazydog",
+    "maytheforcebewithyou",
+    "ithinkthereforeiam",
+    "allyouneedislove",
+    "letitbe",
+    "letitgo",
+    "hakunamatata",
+    "winteriscoming",
+    "justdoit",
+    "opensesame",
+    "carpediem",
+    "livelaughlove",
+    "iloveyou",
+    "trustno",
+    "thetruthisoutthere",
+    "onceuponatime",
+    "helloworld",
+    "whatwouldjesusdo",
+    "neverforget",
+    "youonlyliveonce",
+    "inheritthewind",
+    "somewhereovertherainbow",
+];
+
+/// Passwords so common that every attacker tries them first
+const COMMON_PASSWORDS: [&str; 20] = [
+    "password", "passwort", "qwerty", "letmein", "welcome", "admin", "monkey", "dragon", "master",
+    "football", "baseball", "sunshine", "princess", "shadow", "superman", "batman", "michael",
+    "starwars", "whatever", "secret",
+];
+
+/// What leetspeak characters stand for, most likely reading first
+const LEET: [(char, &str); 13] = [
+    ('4', "a"), ('@', "a"), ('3', "e"), ('1', "il"), ('!', "i"), ('|', "l"), ('0', "o"),
+    ('5', "s"), ('$', "s"), ('7', "t"), ('+', "t"), ('8', "b"), ('9', "g"),
+];
+
+/// Hashes of the entries of quotes.txt and of every three words in a row in
+/// them, as little-endian u32s sorted for binary search
+#[cfg(feature = "quotes")]
+static QUOTE_NGRAMS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/quote_ngrams.bin"));
+
+/// Keyboard layouts whose walks are recognized
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    Qwerty,
+    Azerty,
+}
+
+impl Layout {
+    /// Rows of keys, unshifted except for the digits of AZERTY
+    fn rows(self) -> &'static [&'static str] {
+        match self {
+            Layout::Qwerty => &["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"],
+            Layout::Azerty => &["1234567890", "azertyuiop", "qsdfghjklm", "wxcvbn,;:!"],
+        }
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Layout::Qwerty => "QWERTY",
+            Layout::Azerty => "AZERTY",
+        })
+    }
+}
+
+const LAYOUTS: [Layout; 2] = [Layout::Qwerty, Layout::Azerty];
+/// Roughly how many keys a walk can start from
+const WALK_STARTS: f64 = 47.0;
+/// Years an attacker tries, as plausible_year allows
+const YEARS: f64 = 200.0;
+/// Ways a date is commonly written: orders and delimiters
+const DATE_FORMATS: f64 = 8.0;
+/// Roughly how many well known phrases an attacker tries first
+const KNOWN_PHRASES: f64 = 1e6;
+/// Letters and digits a sequence can start from
+const SEQUENCE_STARTS: f64 = 36.0;
+/// Steps a sequence can take: one or two up or down
+const SEQUENCE_STEPS: f64 = 4.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Weakness {
+    /// The phrase contains a well known quote, lyric or saying
+    Quote(&'static str),
+    /// The phrase contains these words in the order a well known quote,
+    /// lyric or title has them
+    KnownText(String),
+    /// The phrase contains a run of neighbouring keys on this layout
+    KeyboardWalk(String, Layout),
+    /// The phrase contains something that reads as a year
+    Year(String),
+    /// The phrase contains something that reads as a date
+    Date(String),
+    /// The phrase repeats this text this many times in a row
+    Repeat(String, usize),
+    /// The phrase uses this word this many times
+    RepeatedWord(String, usize),
+    /// The phrase contains a run of evenly spaced letters or digits, as in
+    /// abcd or 1357
+    Sequence(String),
+    /// The phrase contains this text, which is the word written in leetspeak
+    Leet(String, String),
+    /// The phrase contains a term from the named list
+    Term(String, String),
+    /// The phrase has only this many words
+    FewWords(usize),
+}
+
+impl Weakness {
+    /// What to do instead
+    pub fn suggestion(&self) -> &'static str {
+        match self {
+            Weakness::Quote(_) | Weakness::KnownText(_) => "Use words that have never been written together, such as randomly chosen ones.",
+            Weakness::KeyboardWalk(..) => "Drop the key sequence; it adds almost nothing.",
+            Weakness::Year(_) | Weakness::Date(_) => "Leave out dates, which are guessed early and are often public.",
+            Weakness::Repeat(..) | Weakness::RepeatedWord(..) => "Use something new instead of repeating; a repeat costs an attacker one extra guess.",
+            Weakness::Sequence(_) => "Drop the sequence; it is guessed almost as fast as its first character.",
+            Weakness::Leet(..) => "Swapping letters for look-alike symbols is one of the first things tried; use more words instead.",
+            Weakness::Term(..) => "Leave out words an attacker tries first, such as common passwords and names tied to you or your organization.",
+            Weakness::FewWords(_) => "Add more unrelated words, or generate a passphrase instead.",
+        }
+    }
+
+    /// The text of the phrase the weakness covers, if it is one part of it
+    pub fn token(&self) -> Option<&str> {
+        match self {
+            Weakness::Quote(text) => Some(text),
+            Weakness::KnownText(text) | Weakness::KeyboardWalk(text, _) | Weakness::Year(text)
+                | Weakness::Date(text) | Weakness::Sequence(text) | Weakness::Leet(text, _)
+                | Weakness::Term(text, _) => Some(text),
+            Weakness::Repeat(..) | Weakness::RepeatedWord(..) | Weakness::FewWords(_) => None,
+        }
+    }
+
+    /// Short description for a breakdown
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Weakness::Quote(_) | Weakness::KnownText(_) => "well known phrase",
+            Weakness::KeyboardWalk(..) => "keyboard walk",
+            Weakness::Year(_) => "year",
+            Weakness::Date(_) => "date",
+            Weakness::Repeat(..) => "repetition",
+            Weakness::RepeatedWord(..) => "repeated word",
+            Weakness::Sequence(_) => "sequence",
+            Weakness::Leet(..) => "leetspeak word",
+            Weakness::Term(..) => "known term",
+            Weakness::FewWords(_) => "few words",
+        }
+    }
+
+    /// Characters of the phrase the weakness covers
+    fn len(&self) -> usize {
+        match self {
+            Weakness::Repeat(unit, count) | Weakness::RepeatedWord(unit, count) => unit.chars().count() * count,
+            _ => self.token().map_or(0, |token| token.chars().count()),
+        }
+    }
+}
+
+/// A weakness a matcher found
+#[derive(Clone, Debug)]
+pub struct Match {
+    pub weakness: Weakness,
+    /// Bits an attacker who knows the pattern needs to guess what it covers
+    pub bits: f64,
+}
+
+/// Finds one kind of weakness. Implement it to check for patterns of your
+/// own, such as product names or office locations, and add it to a Checker.
+pub trait Matcher {
+    fn find(&self, phrase: &str) -> Vec<Match>;
+}
+
+/// A weakness and what it costs
+#[derive(Clone, Debug)]
+pub struct Finding {
+    pub weakness: Weakness,
+    /// Bits the characters it covers would be worth if they were random
+    pub naive_bits: f64,
+    /// Bits they are worth to an attacker who tries the pattern
+    pub bits: f64,
+}
+
+/// A stretch of a phrase and what it adds to the guessing
+#[derive(Clone, Debug)]
+pub struct Segment {
+    pub text: String,
+    /// What the stretch looks like, e.g. "dictionary word" or "3 digits"
+    pub kind: String,
+    pub bits: f64,
+}
+
+/// How hard a chosen phrase is to guess
+#[derive(Clone, Debug)]
+pub struct Assessment {
+    pub findings: Vec<Finding>,
+    /// The whole phrase, cut into the pieces an attacker would guess
+    /// separately
+    pub segments: Vec<Segment>,
+    /// Bits against an attacker trying every string of its length over the
+    /// classes of character it uses
+    pub brute_force_bits: f64,
+    /// Sum of the segments' bits
+    pub bits: f64,
+}
+
+impl Assessment {
+    /// Guesses an attacker who tries the patterns needs to be sure of the
+    /// phrase
+    pub fn guesses(&self) -> f64 {
+        self.bits.exp2()
+    }
+}
+
+/// How fast an attacker can guess
+#[derive(Clone, Copy, Debug)]
+pub struct Attack {
+    pub name: &'static str,
+    pub guesses_per_second: f64,
+}
+
+/// The attack models zxcvbn reports, from a rate-limited login form to a
+/// leaked database of fast, unsalted hashes
+pub const ATTACKS: [Attack; 4] = [
+    Attack { name: "online, throttled", guesses_per_second: 100.0 / 3600.0 },
+    Attack { name: "online, unthrottled", guesses_per_second: 10.0 },
+    Attack { name: "offline, slow hash", guesses_per_second: 1e4 },
+    Attack { name: "offline, fast hash", guesses_per_second: 1e10 },
+];
+
+/// An account that locks for a while after so many failed tries, which
+/// limits online guessing however fast the attacker is
+#[derive(Clone, Copy, Debug)]
+pub struct Lockout {
+    pub tries: u32,
+    /// How long until the tries are allowed again
+    pub window: std::time::Duration,
+}
+
+impl Lockout {
+    /// Average seconds to find a phrase that takes `guesses` to be sure of.
+    /// The guesses themselves take no time next to the lockouts.
+    pub fn average_seconds(&self, guesses: f64) -> f64 {
+        let windows = (guesses / 2.0 / self.tries as f64).ceil();
+        (windows - 1.0).max(0.0) * self.window.as_secs_f64()
+    }
+}
+
+impl fmt::Display for Lockout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} tries per {}", self.tries, humantime::format_duration(self.window))
+    }
+}
+
+/// Entropy of `phrase` against an attacker trying every string of its length
+/// over the character classes it uses
+pub fn brute_force_entropy(phrase: &str) -> f64 {
+    let has = |class: fn(&char) -> bool| phrase.chars().any(|c| class(&c));
+    let mut charset = 0.0;
+    if has(char::is_ascii_lowercase) { charset += 26.0; }
+    if has(char::is_ascii_uppercase) { charset += 26.0; }
+    if has(char::is_ascii_digit) { charset += 10.0; }
+    if has(char::is_ascii_punctuation) { charset += 32.0; }
+    if phrase.contains(' ') { charset += 1.0; }
+    // Anything else is at least one more symbol
+    if phrase.chars().any(|c| !c.is_ascii_graphic() && c != ' ') {
+        charset += 1.0;
+    }
+    phrase.chars().count() as f64 * f64::max(charset, 1.0).log2()
+}
+
+impl fmt::Display for Weakness {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Weakness::Quote(quote) => write!(f, "contains a well known saying ({})", quote),
+            Weakness::KnownText(text) => write!(f, "contains \"{}\", found in well known quotes, lyrics or titles", text),
+            Weakness::KeyboardWalk(walk, layout) => write!(f, "contains the {} keyboard walk {}", layout, walk),
+            Weakness::Year(year) => write!(f, "contains {}, which looks like a year", year),
+            Weakness::Date(date) => write!(f, "contains {}, which looks like a date", date),
+            Weakness::Repeat(unit, count) => write!(f, "repeats {} {} times", unit, count),
+            Weakness::RepeatedWord(word, count) => write!(f, "uses the word {} {} times", word, count),
+            Weakness::Sequence(sequence) => write!(f, "contains the sequence {}", sequence),
+            Weakness::Leet(text, word) => write!(f, "contains {}, which is {} in leetspeak", text, word),
+            Weakness::Term(term, list) => write!(f, "contains {}, from {}", term, list),
+            Weakness::FewWords(1) => write!(f, "has only one word"),
+            Weakness::FewWords(count) => write!(f, "has only {} words", count),
+        }
+    }
+}
+
+/// Words of `phrase`, split at anything but letters and at lowercase to
+/// uppercase changes, so that CamelCase counts as several words
+pub fn words(phrase: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in phrase.chars() {
+        let boundary = !c.is_alphabetic() || (c.is_uppercase() && previous_lower);
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        if c.is_alphabetic() {
+            current.push(c);
+        }
+        previous_lower = c.is_lowercase();
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn quote(phrase: &str) -> Option<Weakness> {
+    let letters: String = phrase.chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect();
+    QUOTES.iter().find(|quote| letters.contains(*quote)).map(|quote| Weakness::Quote(quote))
+}
+
+/// FNV-1a, as build.rs hashes the corpus with
+#[cfg(feature = "quotes")]
+fn hash(text: &str) -> u32 {
+    text.bytes().fold(0x811c_9dc5, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193))
+}
+
+#[cfg(feature = "quotes")]
+fn in_corpus(words: &[String]) -> bool {
+    let target = hash(&words.join(" "));
+    let at = |i: usize| u32::from_le_bytes([
+        QUOTE_NGRAMS[4 * i], QUOTE_NGRAMS[4 * i + 1], QUOTE_NGRAMS[4 * i + 2], QUOTE_NGRAMS[4 * i + 3],
+    ]);
+    let (mut low, mut high) = (0, QUOTE_NGRAMS.len() / 4);
+    while low < high {
+        let mid = (low + high) / 2;
+        match at(mid).cmp(&target) {
+            std::cmp::Ordering::Less => low = mid + 1,
+            std::cmp::Ordering::Greater => high = mid,
+            std::cmp::Ordering::Equal => return true,
+        }
+    }
+    false
+}
+
+/// Words as build.rs splits the corpus: lowercased, with apostrophes dropped
+/// so that "don't" and "dont" agree
+#[cfg(feature = "quotes")]
+fn corpus_words(phrase: &str) -> Vec<String> {
+    phrase.replace(['\'', '\u{2019}'], "")
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Looks the phrase up in the quote corpus: all of it if it is at most three
+/// words, or else four words in a row, whose two overlapping trigrams must
+/// both be known, which keeps hash collisions from being reported
+#[cfg(feature = "quotes")]
+fn known_text(phrase: &str) -> Option<Weakness> {
+    let words = corpus_words(phrase);
+    if words.len() <= 3 {
+        return (!words.is_empty() && in_corpus(&words)).then(|| Weakness::KnownText(words.join(" ")));
+    }
+    let start = (0..=words.len() - 4).find(|&i| in_corpus(&words[i..i + 3]) && in_corpus(&words[i + 1..i + 4]))?;
+    // Report as much of the quote as the phrase has
+    let mut end = start + 4;
+    while end < words.len() && in_corpus(&words[end - 2..=end]) {
+        end += 1;
+    }
+    Some(Weakness::KnownText(words[start..end].join(" ")))
+}
+
+#[cfg(not(feature = "quotes"))]
+fn known_text(_phrase: &str) -> Option<Weakness> {
+    None
+}
+
+/// Well known sayings, and with the quotes feature, quotes, lyrics and titles
+pub struct Quotes;
+
+impl Matcher for Quotes {
+    fn find(&self, phrase: &str) -> Vec<Match> {
+        quote(phrase).or_else(|| known_text(phrase)).into_iter()
+            .map(|weakness| Match { weakness, bits: KNOWN_PHRASES.log2() })
+            .collect()
+    }
+}
+
+/// Whether `b` is next to `a` on the same row of `layout`, in either direction
+fn adjacent(layout: Layout, a: char, b: char) -> bool {
+    layout.rows().iter().any(|row| {
+        let keys: Vec<char> = row.chars().collect();
+        keys.windows(2).any(|pair| pair == [a, b] || pair == [b, a])
+    })
+}
+
+/// Runs of neighbouring keys on any layout. A walk found on more than one
+/// layout, or inside a longer walk on another, is reported once.
+fn keyboard_walks(phrase: &str) -> Vec<Weakness> {
+    let keys: Vec<char> = phrase.chars().flat_map(char::to_lowercase).collect();
+    let mut walks: Vec<Weakness> = Vec::new();
+    for layout in LAYOUTS {
+        let mut start = 0;
+        for end in 1..=keys.len() {
+            // A walk keeps one direction; turning back starts a new one
+            let continues = end < keys.len() && adjacent(layout, keys[end - 1], keys[end])
+                && (end - start < 2 || keys[end] != keys[end - 2]);
+            if !continues {
+                if end - start >= MIN_WALK {
+                    walks.push(Weakness::KeyboardWalk(keys[start..end].iter().collect(), layout));
+                }
+                start = end;
+            }
+        }
+    }
+
+    let texts: Vec<String> = walks.iter().filter_map(|walk| walk.token()).map(str::to_string).collect();
+    let mut kept: Vec<Weakness> = Vec::new();
+    for walk in walks {
+        let text = walk.token().unwrap_or("");
+        let longer = texts.iter().any(|other| other.len() > text.len() && other.contains(text));
+        if !longer && !kept.iter().any(|found| found.token() == Some(text)) {
+            kept.push(walk);
+        }
+    }
+    kept
+}
+
+/// Runs of neighbouring keys, which may start anywhere and go either way
+pub struct KeyboardWalks;
+
+impl Matcher for KeyboardWalks {
+    fn find(&self, phrase: &str) -> Vec<Match> {
+        keyboard_walks(phrase).into_iter()
+            .map(|weakness| {
+                let length = weakness.len() as f64;
+                Match { weakness, bits: (LAYOUTS.len() as f64 * WALK_STARTS * 2.0 * length).log2() }
+            })
+            .collect()
+    }
+}
+
+/// Text repeated back to back, as in aaaa or abcabc, and words that appear
+/// more than once anywhere
+fn repeats(phrase: &str) -> Vec<Weakness> {
+    let chars: Vec<char> = phrase.chars().flat_map(char::to_lowercase).collect();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        // The repeating unit that covers the most characters from here
+        let best = (1..=(chars.len() - i) / 2)
+            .map(|period| {
+                let unit = &chars[i..i + period];
+                let count = chars[i..].chunks_exact(period).take_while(|chunk| chunk == &unit).count();
+                (period, count)
+            })
+            .filter(|&(period, count)| count >= if period == 1 { 3 } else { 2 })
+            // Of units covering as much, the shortest, so aaaa is a four times
+            .max_by_key(|&(period, count)| (period * count, std::cmp::Reverse(period)));
+        match best {
+            Some((period, count)) => {
+                found.push(Weakness::Repeat(chars[i..i + period].iter().collect(), count));
+                i += period * count;
+            }
+            None => i += 1,
+        }
+    }
+
+    let words: Vec<String> = words(phrase).iter().map(|word| word.to_lowercase()).collect();
+    let mut seen: Vec<&String> = Vec::new();
+    for word in &words {
+        let count = words.iter().filter(|other| *other == word).count();
+        let adjacent = found.iter().any(|weakness| matches!(weakness, Weakness::Repeat(unit, _) if unit.contains(word.as_str())));
+        if count > 1 && !seen.contains(&word) && !adjacent {
+            found.push(Weakness::RepeatedWord(word.clone(), count));
+        }
+        seen.push(word);
+    }
+    found
+}
+
+/// Repeated text and words
+pub struct Repeats;
+
+impl Matcher for Repeats {
+    fn find(&self, phrase: &str) -> Vec<Match> {
+        let per_char = brute_force_entropy(phrase) / phrase.chars().count().max(1) as f64;
+        repeats(phrase).into_iter()
+            .map(|weakness| {
+                // The first copy counts in full, each repeat barely at all
+                let bits = match &weakness {
+                    Weakness::Repeat(unit, count) | Weakness::RepeatedWord(unit, count) => {
+                        unit.chars().count() as f64 * per_char + (*count as f64).log2()
+                    }
+                    _ => 0.0,
+                };
+                Match { weakness, bits }
+            })
+            .collect()
+    }
+}
+
+fn plausible_date(day: u32, month: u32) -> bool {
+    (1..=31).contains(&day) && (1..=12).contains(&month)
+}
+
+fn plausible_year(year: u32) -> bool {
+    (1900..=2099).contains(&year)
+}
+
+/// Whether a run of digits reads as an undelimited date such as 19840412,
+/// 12041984 or 120484
+fn date_digits(digits: &str) -> bool {
+    let number = |range: std::ops::Range<usize>| digits[range].parse::<u32>().unwrap_or(0);
+    match digits.len() {
+        6 => plausible_date(number(0..2), number(2..4)) || plausible_date(number(2..4), number(0..2)),
+        8 => (plausible_year(number(0..4)) && plausible_date(number(6..8), number(4..6)))
+            || (plausible_year(number(4..8))
+                && (plausible_date(number(0..2), number(2..4)) || plausible_date(number(2..4), number(0..2)))),
+        _ => false,
+    }
+}
+
+fn dates(phrase: &str) -> Vec<Weakness> {
+    let chars: Vec<char> = phrase.chars().collect();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() {
+            i += 1;
+            continue;
+        }
+        // Digit groups joined by a date delimiter, as in 4/12/1984
+        let start = i;
+        let mut groups = Vec::new();
+        loop {
+            let group_start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            groups.push(chars[group_start..i].iter().collect::<String>());
+            let delimited = i + 1 < chars.len() && matches!(chars[i], '/' | '-' | '.') && chars[i + 1].is_ascii_digit();
+            if !delimited || groups.len() == 3 {
+                break;
+            }
+            i += 1;
+        }
+
+        let text: String = chars[start..i].iter().collect();
+        let delimited_date = groups.len() == 3 && {
+            let n: Vec<u32> = groups.iter().map(|g| g.parse().unwrap_or(0)).collect();
+            (groups[0].len() == 4 && plausible_year(n[0]) && plausible_date(n[2], n[1]))
+                || (groups[2].len() <= 4 && (plausible_date(n[0], n[1]) || plausible_date(n[1], n[0])))
+        };
+        if groups.len() == 1 && text.len() == 4 && plausible_year(text.parse().unwrap_or(0)) {
+            found.push(Weakness::Year(text));
+        } else if delimited_date || (groups.len() == 1 && date_digits(&text)) {
+            found.push(Weakness::Date(text));
+        }
+    }
+    found
+}
+
+/// Years and dates, delimited or not
+pub struct Dates;
+
+impl Matcher for Dates {
+    fn find(&self, phrase: &str) -> Vec<Match> {
+        dates(phrase).into_iter()
+            .map(|weakness| {
+                let bits = match weakness {
+                    Weakness::Year(_) => YEARS.log2(),
+                    _ => (366.0 * YEARS * DATE_FORMATS).log2(),
+                };
+                Match { weakness, bits }
+            })
+            .collect()
+    }
+}
+
+/// Runs of letters or digits a step of one or two apart, either way, as in
+/// abcd, 4321 or 1357
+pub struct Sequences;
+
+impl Matcher for Sequences {
+    fn find(&self, phrase: &str) -> Vec<Match> {
+        let chars: Vec<char> = phrase.chars().flat_map(char::to_lowercase).collect();
+        let class = |c: char| if c.is_ascii_lowercase() { 1 } else if c.is_ascii_digit() { 2 } else { 0 };
+        let step = |a: char, b: char| (class(a) != 0 && class(a) == class(b)).then(|| b as i32 - a as i32)
+            .filter(|step| (1..=2).contains(&step.abs()));
+        let mut found = Vec::new();
+        let mut start = 0;
+        while start + 1 < chars.len() {
+            let first = match step(chars[start], chars[start + 1]) {
+                Some(first) => first,
+                None => {
+                    start += 1;
+                    continue;
+                }
+            };
+            let mut end = start + 2;
+            while end < chars.len() && step(chars[end - 1], chars[end]) == Some(first) {
+                end += 1;
+            }
+            if end - start >= MIN_SEQUENCE {
+                let bits = (SEQUENCE_STARTS * SEQUENCE_STEPS * (end - start) as f64).log2();
+                found.push(Match { weakness: Weakness::Sequence(chars[start..end].iter().collect()), bits });
+                start = end;
+            } else {
+                start += 1;
+            }
+        }
+        found
+    }
+}
+
+/// Words written with digits and symbols for some of their letters, as in
+/// p4ssw0rd
+pub struct Leet {
+    words: HashSet<String>,
+}
+
+impl Leet {
+    /// Looks for leetspeak spellings of `words`, which should be lowercase
+    pub fn new(words: HashSet<String>) -> Leet {
+        Leet { words }
+    }
+
+    /// The ways `text` reads with its leetspeak undone: each character's
+    /// first reading, then its second where it has one
+    fn readings(text: &[char]) -> [String; 2] {
+        let read = |c: char, which: usize| LEET.iter()
+            .find(|(leet, _)| *leet == c)
+            .map_or(c, |(_, letters)| letters.chars().nth(which).unwrap_or_else(|| letters.chars().next().unwrap_or(c)));
+        [0, 1].map(|which| text.iter().map(|&c| read(c, which)).collect())
+    }
+}
+
+impl Matcher for Leet {
+    fn find(&self, phrase: &str) -> Vec<Match> {
+        let chars: Vec<char> = phrase.chars().flat_map(char::to_lowercase).collect();
+        let leet = |c: char| LEET.iter().any(|(l, _)| *l == c);
+        let mut found = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            // The longest word from here that needs some leetspeak undone
+            let word = (start + MIN_TERM..=chars.len()).rev().find_map(|end| {
+                let text = &chars[start..end];
+                if !text.iter().all(|&c| c.is_alphabetic() || leet(c)) || !text.iter().any(|&c| leet(c)) {
+                    return None;
+                }
+                Leet::readings(text).iter().find(|reading| self.words.contains(*reading)).map(|word| (end, word.clone()))
+            });
+            match word {
+                Some((end, word)) => {
+                    let swapped = chars[start..end].iter().filter(|&&c| leet(c)).count();
+                    let bits = (self.words.len() as f64).log2() + swapped as f64;
+                    found.push(Match { weakness: Weakness::Leet(chars[start..end].iter().collect(), word), bits });
+                    start = end;
+                }
+                None => start += 1,
+            }
+        }
+        found
+    }
+}
+
+/// Terms from a list, found anywhere in the phrase and regardless of case,
+/// spaces and punctuation
+pub struct Terms {
+    /// How reports refer to the list, e.g. "the common passwords"
+    name: String,
+    terms: Vec<String>,
+}
+
+impl Terms {
+    pub fn new<T: AsRef<str>>(name: &str, terms: impl IntoIterator<Item = T>) -> Terms {
+        let mut terms: Vec<String> = terms.into_iter()
+            .map(|term| term.as_ref().chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect())
+            .filter(|term: &String| term.chars().count() >= MIN_TERM)
+            .collect();
+        // Longest first, so a term is reported rather than one inside it
+        terms.sort_by_key(|term| std::cmp::Reverse(term.chars().count()));
+        terms.dedup();
+        Terms { name: name.to_string(), terms }
+    }
+}
+
+impl Matcher for Terms {
+    fn find(&self, phrase: &str) -> Vec<Match> {
+        let mut text: String = phrase.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect();
+        let bits = (self.terms.len() as f64).log2().max(1.0);
+        let mut found = Vec::new();
+        for term in &self.terms {
+            if text.contains(term.as_str()) {
+                // Blank it out so that terms inside it are not reported too
+                text = text.replacen(term.as_str(), " ", 1);
+                found.push(Match { weakness: Weakness::Term(term.clone(), self.name.clone()), bits });
+            }
+        }
+        found
+    }
+}
+
+/// Where `token` appears in `chars`, ignoring case and, for tokens of letters
+/// found apart from their spaces such as quotes, anything but letters and digits
+fn locate(chars: &[char], token: &str) -> Option<(usize, usize)> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let token: Vec<char> = token.chars().collect();
+    if let Some(start) = lower.windows(token.len().max(1)).position(|window| window == &token[..]) {
+        return Some((start, start + token.len()));
+    }
+
+    let kept: Vec<usize> = (0..lower.len()).filter(|&i| lower[i].is_alphanumeric()).collect();
+    let wanted: Vec<char> = token.iter().copied().filter(|c| c.is_alphanumeric()).collect();
+    if wanted.is_empty() || wanted.len() > kept.len() {
+        return None;
+    }
+    (0..=kept.len() - wanted.len())
+        .find(|&k| (0..wanted.len()).all(|j| lower[kept[k + j]] == wanted[j]))
+        .map(|k| (kept[k], kept[k + wanted.len() - 1] + 1))
+}
+
+/// Splits text no weakness covers into words, digit runs and symbols, and
+/// prices each as zxcvbn-style matchers would
+fn plain_segments(text: &str, dictionary: &HashSet<String>, seen: &mut Vec<String>) -> Vec<Segment> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let class = |c: char| if c.is_alphabetic() { 0 } else if c.is_ascii_digit() { 1 } else { 2 };
+        let start = i;
+        while i < chars.len() && class(chars[i]) == class(chars[start]) {
+            i += 1;
+        }
+        let run: String = chars[start..i].iter().collect();
+        let len = i - start;
+        let segment = match class(chars[start]) {
+            0 => {
+                let lower = run.to_lowercase();
+                // Capitalizing the first letter or all of them is tried early
+                let case_bits = if run == lower { 0.0 } else if run.chars().skip(1).all(char::is_lowercase)
+                    || run.chars().all(char::is_uppercase) { 1.0 } else { len as f64 };
+                if seen.contains(&lower) {
+                    Segment { text: run, kind: "repeat of an earlier word".to_string(), bits: 1.0 }
+                } else if dictionary.contains(&lower) {
+                    seen.push(lower);
+                    Segment { text: run, kind: "dictionary word".to_string(),
+                        bits: (dictionary.len() as f64).log2() + case_bits }
+                } else {
+                    seen.push(lower);
+                    Segment { text: run, kind: format!("{} letters", len), bits: len as f64 * 26f64.log2() + case_bits }
+                }
+            }
+            1 => Segment { text: run, kind: format!("{} digits", len), bits: len as f64 * 10f64.log2() },
+            // One separator used throughout is a single choice
+            _ if len == 1 && seen.contains(&run) => Segment { text: run, kind: "same separator".to_string(), bits: 0.0 },
+            _ => {
+                seen.push(run.clone());
+                let kind = if run.trim().is_empty() { "space".to_string() } else { format!("{} symbols", len) };
+                Segment { text: run, kind, bits: len as f64 * 33f64.log2() }
+            }
+        };
+        segments.push(segment);
+    }
+    segments
+}
+
+/// Runs matchers over phrases. The built-in ones come first, and any added
+/// with `with` after them.
+pub struct Checker {
+    dictionary: HashSet<String>,
+    matchers: Vec<Box<dyn Matcher>>,
+}
+
+impl Checker {
+    /// A checker with the built-in matchers, which prices words of
+    /// `dictionary` as such and recognizes them in leetspeak
+    pub fn new(dictionary: HashSet<String>) -> Checker {
+        let leet = dictionary.iter().cloned().chain(COMMON_PASSWORDS.iter().map(|word| word.to_string())).collect();
+        Checker {
+            dictionary,
+            matchers: vec![
+                Box::new(Quotes),
+                Box::new(Terms::new("the common passwords", COMMON_PASSWORDS)),
+                Box::new(Leet::new(leet)),
+                Box::new(KeyboardWalks),
+                Box::new(Sequences),
+                Box::new(Dates),
+                Box::new(Repeats),
+            ],
+        }
+    }
+
+    /// Adds a matcher, run after those already added
+    pub fn with(mut self, matcher: impl Matcher + 'static) -> Checker {
+        self.matchers.push(Box::new(matcher));
+        self
+    }
+
+    /// Every weakness found in `phrase`, most serious first. Text an earlier
+    /// matcher reported is not reported again for part of it.
+    pub fn weaknesses(&self, phrase: &str) -> Vec<Match> {
+        let mut found: Vec<Match> = Vec::new();
+        for matcher in &self.matchers {
+            for candidate in matcher.find(phrase) {
+                let covered = candidate.weakness.token().is_some_and(|token| {
+                    let token = token.to_lowercase();
+                    found.iter().filter_map(|earlier| earlier.weakness.token())
+                        .any(|earlier| earlier.to_lowercase().contains(&token))
+                });
+                if !covered {
+                    found.push(candidate);
+                }
+            }
+        }
+        let count = words(phrase).len();
+        if count < MIN_WORDS {
+            found.push(Match { weakness: Weakness::FewWords(count), bits: 0.0 });
+        }
+        found
+    }
+
+    /// Finds the weaknesses of `phrase`, estimating how many bits of guessing
+    /// each leaves of the characters it covers, and cuts the phrase into priced
+    /// segments: weaknesses, dictionary words, other letters, digits and symbols
+    pub fn assess(&self, phrase: &str) -> Assessment {
+        let brute_force_bits = brute_force_entropy(phrase);
+        let per_char = brute_force_bits / phrase.chars().count().max(1) as f64;
+        let findings: Vec<Finding> = self.weaknesses(phrase).into_iter()
+            .map(|Match { weakness, bits }| {
+                let naive_bits = weakness.len() as f64 * per_char;
+                Finding { bits: bits.min(naive_bits), weakness, naive_bits }
+            })
+            .collect();
+
+        let chars: Vec<char> = phrase.chars().collect();
+        let mut spans: Vec<(usize, usize, &Finding)> = Vec::new();
+        for finding in &findings {
+            let token = match &finding.weakness {
+                Weakness::Repeat(unit, count) => unit.repeat(*count),
+                weakness => match weakness.token() {
+                    Some(token) => token.to_string(),
+                    None => continue,
+                },
+            };
+            if let Some((start, end)) = locate(&chars, &token) {
+                if spans.iter().all(|&(s, e, _)| end <= s || start >= e) {
+                    spans.push((start, end, finding));
+                }
+            }
+        }
+        spans.sort_by_key(|&(start, ..)| start);
+
+        let mut segments = Vec::new();
+        let mut seen = Vec::new();
+        let mut at = 0;
+        for (start, end, finding) in spans {
+            segments.extend(plain_segments(&chars[at..start].iter().collect::<String>(), &self.dictionary, &mut seen));
+            segments.push(Segment {
+                text: chars[start..end].iter().collect(),
+                kind: finding.weakness.kind().to_string(),
+                bits: finding.bits,
+            });
+            at = end;
+        }
+        segments.extend(plain_segments(&chars[at..].iter().collect::<String>(), &self.dictionary, &mut seen));
+
+        let bits = segments.iter().map(|segment| segment.bits).sum();
+        Assessment { findings, segments, brute_force_bits, bits }
+    }
+}
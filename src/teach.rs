@@ -0,0 +1,64 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+
+use passphrs::{join_parts, Part};
+
+/// Settings of the handout's passphrases, used to explain their strength
+pub struct Lesson {
+    pub students: usize,
+    pub list_len: usize,
+    pub length: usize,
+    pub salt_length: usize,
+    pub salt_chars: usize,
+    pub entropy: f64,
+}
+
+/// Prints a numbered sheet of distinct practice passphrases followed by a
+/// short explanation of how their strength is measured
+pub fn handout(generate: &dyn Fn() -> Result<Vec<Part>>, lesson: &Lesson) -> Result<()> {
+    if lesson.entropy.exp2() < lesson.students as f64 {
+        bail!("These settings cannot produce {} distinct passphrases", lesson.students);
+    }
+
+    let mut seen = HashSet::new();
+    let mut phrases = Vec::new();
+    while phrases.len() < lesson.students {
+        let phrase = join_parts(&generate()?);
+        if seen.insert(phrase.clone()) {
+            phrases.push(phrase);
+        }
+    }
+
+    let width = lesson.students.to_string().len();
+    println!("PRACTICE PASSPHRASES - for training only, never use these for real accounts");
+    println!();
+    for (i, phrase) in phrases.iter().enumerate() {
+        println!("{:>width$}. {}", i + 1, phrase, width = width);
+    }
+
+    let word_bits = (lesson.list_len as f64).log2();
+    println!();
+    println!("How strong is a passphrase?");
+    println!();
+    println!("Strength is measured in bits of entropy: the number of times you would have");
+    println!("to double a pile of guesses before it is certain to contain the passphrase.");
+    println!("Every extra bit doubles the work for an attacker.");
+    println!();
+    println!("Each passphrase above was made by picking {} words at random from a list of", lesson.length);
+    println!("{} words. Each word contributes log2({}) = {:.1} bits, however short it is,", lesson.list_len, lesson.list_len, word_bits);
+    println!("because the attacker has to try every word in the list for every position.");
+    if lesson.salt_length > 0 {
+        println!("{} extra random character(s) from a set of {} were added to one word, which", lesson.salt_length, lesson.salt_chars);
+        println!("adds a little more.");
+    }
+    println!();
+    println!("Altogether these passphrases have about {:.0} bits of entropy. An attacker", lesson.entropy);
+    println!("who knows exactly how they were made still needs around 2^{:.0} guesses on average.", lesson.entropy - 1.0);
+    println!();
+    println!("What matters is that the computer, not a person, chose the words. Phrases");
+    println!("people invent, such as song lyrics or quotes, are far easier to guess than");
+    println!("their length suggests because attackers try them first.");
+
+    Ok(())
+}
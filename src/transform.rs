@@ -0,0 +1,212 @@
+//! Steps that turn a list of chosen words into a finished passphrase. Each
+//! one is a [`Transform`], applied in order, so a new kind of mutation is a
+//! new type here rather than another branch in the generator.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use rand::{Rng, RngCore};
+
+use crate::case::{binary_entropy, Case};
+use crate::Part;
+
+/// One step of the generation pipeline
+pub trait Transform: Debug {
+    /// Changes the passphrase in place, drawing any randomness from `rng`
+    fn apply(&self, parts: &mut Vec<Part>, rng: &mut dyn RngCore);
+
+    /// Bits of entropy the step adds, or removes if negative, for passphrases
+    /// of `length` words from `list`
+    fn bits(&self, list: &[String], length: usize) -> f64;
+
+    /// The list as the step leaves its words, so that later steps are
+    /// measured on the words as they will appear. None if it changes none
+    /// of them, or not the same way every time.
+    fn words(&self, _list: &[String]) -> Option<Vec<String>> {
+        None
+    }
+}
+
+/// Cases every word
+#[derive(Debug)]
+pub struct CaseWords(pub Case);
+
+impl Transform for CaseWords {
+    fn apply(&self, parts: &mut Vec<Part>, mut rng: &mut dyn RngCore) {
+        let words = parts.iter_mut().filter_map(|part| match part {
+            Part::Word(word, _) => Some(word),
+            _ => None,
+        });
+        for (n, word) in words.enumerate() {
+            self.0.for_position(n).apply(word, &mut rng);
+        }
+    }
+
+    fn bits(&self, list: &[String], length: usize) -> f64 {
+        length as f64 * self.0.entropy(list)
+    }
+}
+
+/// Adds `length` random characters from `chars` after a random word, or
+/// forms the whole passphrase if there are no words
+#[derive(Debug)]
+pub struct InjectSalt {
+    pub length: usize,
+    pub chars: String,
+}
+
+impl Transform for InjectSalt {
+    fn apply(&self, parts: &mut Vec<Part>, rng: &mut dyn RngCore) {
+        if self.length == 0 {
+            return;
+        }
+        let words: Vec<usize> = parts.iter()
+            .enumerate()
+            .filter(|(_, part)| matches!(part, Part::Word(..)))
+            .map(|(i, _)| i)
+            .collect();
+        let at = if words.is_empty() { parts.len() } else { words[rng.gen_range(0..words.len())] + 1 };
+
+        let chars: Vec<char> = self.chars.chars().collect();
+        let salt = (0..self.length).map(|_| chars[rng.gen_range(0..chars.len())]).collect();
+        parts.insert(at, Part::Salt(salt));
+    }
+
+    fn bits(&self, _list: &[String], length: usize) -> f64 {
+        if self.length == 0 {
+            return 0.0;
+        }
+        (length.max(1) as f64).log2() + self.length as f64 * (self.chars.chars().count() as f64).log2()
+    }
+}
+
+/// Cuts every word to its first `.0` characters. Words sharing a prefix
+/// become indistinguishable, which costs entropy.
+#[derive(Debug)]
+pub struct Truncate(pub usize);
+
+impl Truncate {
+    pub fn word(&self, word: &str) -> String {
+        word.chars().take(self.0).collect()
+    }
+}
+
+impl Transform for Truncate {
+    fn apply(&self, parts: &mut Vec<Part>, _rng: &mut dyn RngCore) {
+        for part in parts.iter_mut() {
+            if let Part::Word(word, _) = part {
+                *word = self.word(word);
+            }
+        }
+    }
+
+    fn bits(&self, list: &[String], length: usize) -> f64 {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for word in list {
+            *counts.entry(self.word(word)).or_default() += 1;
+        }
+        let n = list.len() as f64;
+        let truncated: f64 = counts.values().map(|&k| -(k as f64 / n) * (k as f64 / n).log2()).sum();
+        length as f64 * (truncated - n.log2())
+    }
+
+    fn words(&self, list: &[String]) -> Option<Vec<String>> {
+        Some(list.iter().map(|w| self.word(w)).collect())
+    }
+}
+
+/// Letters --substitute replaces when no map is given
+pub const DEFAULT_SUBSTITUTIONS: &str = "a=@,e=3,i=1,o=0,s=$,t=7";
+
+/// Letters and what --substitute may replace them with
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubstitutionMap(pub Vec<(char, String)>);
+
+/// Parses a substitution map such as `a=@,e=3`. Letters are matched
+/// without regard to case.
+pub fn parse_substitutions(s: &str) -> Result<SubstitutionMap, String> {
+    let mut map: Vec<(char, String)> = Vec::new();
+    for entry in s.split(',') {
+        let (from, to) = entry.split_once('=').ok_or_else(|| format!("expected LETTER=REPLACEMENT, not {}", entry))?;
+        let mut from_chars = from.chars();
+        let from = match (from_chars.next(), from_chars.next()) {
+            (Some(c), None) if c.is_alphabetic() => c.to_lowercase().next().unwrap_or(c),
+            _ => return Err(format!("{} is not a single letter", from)),
+        };
+        // A replacement that is itself a letter could not be told apart
+        // from the word, so the choice would add nothing
+        if to.is_empty() || to.chars().any(char::is_alphabetic) {
+            return Err(format!("{} must be replaced with something other than letters", from));
+        }
+        if map.iter().any(|(c, _)| *c == from) {
+            return Err(format!("{} is substituted twice", from));
+        }
+        map.push((from, to.to_string()));
+    }
+    Ok(SubstitutionMap(map))
+}
+
+/// Replaces each letter that has a substitution with probability `prob`,
+/// as in p@ssw0rd. Which letters were replaced is random, so each
+/// replaceable letter adds a choice of entropy.
+#[derive(Debug)]
+pub struct Substitute {
+    pub map: SubstitutionMap,
+    pub prob: f64,
+}
+
+impl Substitute {
+    fn replacement(&self, c: char) -> Option<&str> {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        self.map.0.iter().find(|(from, _)| *from == lower).map(|(_, to)| to.as_str())
+    }
+}
+
+impl Transform for Substitute {
+    fn apply(&self, parts: &mut Vec<Part>, rng: &mut dyn RngCore) {
+        for part in parts.iter_mut() {
+            if let Part::Word(word, _) = part {
+                *word = word.chars()
+                    .map(|c| match self.replacement(c) {
+                        Some(to) if rng.gen_bool(self.prob) => to.to_string(),
+                        _ => c.to_string(),
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    /// Words are equally likely, so each adds the average over the list,
+    /// as Truncate counts its merged prefixes
+    fn bits(&self, list: &[String], length: usize) -> f64 {
+        if list.is_empty() {
+            return 0.0;
+        }
+        let replaceable: usize = list.iter()
+            .map(|w| w.chars().filter(|c| self.replacement(*c).is_some()).count())
+            .sum();
+        length as f64 * replaceable as f64 / list.len() as f64 * binary_entropy(self.prob)
+    }
+}
+
+/// Replaces every separator with one drawn from `chars`, a new draw for
+/// each gap between words
+#[derive(Debug)]
+pub struct RandomSeparators {
+    pub chars: String,
+}
+
+impl Transform for RandomSeparators {
+    fn apply(&self, parts: &mut Vec<Part>, rng: &mut dyn RngCore) {
+        let chars: Vec<char> = self.chars.chars().collect();
+        for part in parts.iter_mut() {
+            if let Part::Separator(separator) = part {
+                *separator = chars[rng.gen_range(0..chars.len())].to_string();
+            }
+        }
+    }
+
+    fn bits(&self, _list: &[String], length: usize) -> f64 {
+        crate::separator_bits(length, self.chars.chars().count())
+    }
+}
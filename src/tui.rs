@@ -0,0 +1,100 @@
+use std::io::Write;
+
+use anyhow::Result;
+use crossterm::cursor::{MoveUp, RestorePosition, SavePosition};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::QueueableCommand;
+
+/// Separators the `s` key cycles through
+const SEPARATORS: [&str; 5] = [" ", "-", ".", "_", ""];
+const MASK: char = '\u{2022}';
+const HELP: &str = "r: new  +/-: length  s: separator  m: show/hide  enter: accept  q: quit";
+
+/// What can be changed while choosing
+pub struct Settings {
+    pub length: usize,
+    pub separator: String,
+}
+
+/// Leaves raw mode however the loop ends
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Result<RawMode> {
+        terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(out: &mut impl Write, candidate: &Result<String>, masked: bool, settings: &Settings, bits: f64) -> Result<()> {
+    out.queue(RestorePosition)?.queue(Clear(ClearType::FromCursorDown))?;
+    let shown = match candidate {
+        Ok(phrase) if masked => phrase.chars().map(|_| MASK).collect(),
+        Ok(phrase) => phrase.clone(),
+        Err(err_) => format!("({})", err_),
+    };
+    // Raw mode does not turn \n into \r\n
+    write!(out, "{}\r\n{} words, separator \"{}\", {:.1} bits\r\n{}",
+        shown, settings.length, settings.separator, bits, HELP)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Shows candidates on stderr, hidden until `m` is pressed, and lets the
+/// length and separator change between them. Returns the accepted
+/// passphrase, or None if the user quit.
+pub fn run(generate: &dyn Fn(&Settings) -> Result<String>, bits: &dyn Fn(usize) -> f64, mut settings: Settings)
+    -> Result<Option<String>> {
+    let mut out = std::io::stderr();
+    // Scroll first if need be, so the saved position stays on screen
+    write!(out, "\n\n\n")?;
+    out.queue(MoveUp(3))?.queue(SavePosition)?;
+    let raw = RawMode::enable()?;
+
+    let mut masked = true;
+    let mut candidate = generate(&settings);
+    let accepted = loop {
+        draw(&mut out, &candidate, masked, &settings, bits(settings.length))?;
+        let key = match event::read()? {
+            Event::Key(KeyEvent { code, modifiers, kind: KeyEventKind::Press, .. }) => (code, modifiers),
+            _ => continue,
+        };
+        match key {
+            (KeyCode::Enter, _) => match candidate {
+                Ok(phrase) => break Some(phrase),
+                Err(_) => continue,
+            },
+            (KeyCode::Char('q'), _) | (KeyCode::Esc, _) => break None,
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => break None,
+            (KeyCode::Char('m'), _) => masked = !masked,
+            (KeyCode::Char('r'), _) | (KeyCode::Char(' '), _) => candidate = generate(&settings),
+            (KeyCode::Char('+'), _) | (KeyCode::Char('='), _) => {
+                settings.length += 1;
+                candidate = generate(&settings);
+            }
+            (KeyCode::Char('-'), _) if settings.length > 1 => {
+                settings.length -= 1;
+                candidate = generate(&settings);
+            }
+            (KeyCode::Char('s'), _) => {
+                let next = SEPARATORS.iter().position(|s| *s == settings.separator).map_or(0, |i| i + 1);
+                settings.separator = SEPARATORS[next % SEPARATORS.len()].to_string();
+                candidate = generate(&settings);
+            }
+            _ => {}
+        }
+    };
+
+    // Leave nothing of the passphrase on screen
+    out.queue(RestorePosition)?.queue(Clear(ClearType::FromCursorDown))?;
+    out.flush()?;
+    drop(raw);
+    Ok(accepted)
+}
@@ -0,0 +1,230 @@
+use clap::ArgEnum;
+
+use passphrs::strength::Lockout;
+
+/// How large numbers and durations are written in reports
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Units {
+    /// Named magnitudes and calendar units, e.g. "3.2 centuries", never in
+    /// scientific notation
+    Human,
+    /// SI prefixes on plain numbers and seconds, e.g. "60.2 Ps"
+    Si,
+    /// Every digit, grouped, with durations in seconds
+    Exact,
+}
+
+/// Words and separators for one language
+pub struct Locale {
+    decimal: char,
+    group: char,
+    /// Names of successive powers of 1000, starting at a thousand
+    magnitudes: [&'static str; 6],
+    seconds: &'static str,
+    minutes: &'static str,
+    hours: &'static str,
+    days: &'static str,
+    years: &'static str,
+    centuries: &'static str,
+    millennia: &'static str,
+    /// A duration as a multiple of the age of the universe, with {} for the
+    /// multiple
+    universe_ages: &'static str,
+    /// A number too large even for stacked magnitudes
+    beyond: &'static str,
+    /// Labels of the --info and --check lines that these numbers appear in
+    pub possible_passphrases: &'static str,
+    pub average_time: &'static str,
+    pub guesses_per_second: &'static str,
+    pub guesses_per_hour: &'static str,
+    pub pattern_guesses: &'static str,
+    pub locked_out: &'static str,
+    pub tries_per: &'static str,
+    /// Names of the attacks in strength::ATTACKS, in order
+    pub attacks: [&'static str; 4],
+}
+
+const ENGLISH: Locale = Locale {
+    decimal: '.',
+    group: ',',
+    magnitudes: ["thousand", "million", "billion", "trillion", "quadrillion", "quintillion"],
+    seconds: "seconds",
+    minutes: "minutes",
+    hours: "hours",
+    days: "days",
+    years: "years",
+    centuries: "centuries",
+    millennia: "millennia",
+    universe_ages: "{} times the age of the universe",
+    beyond: "more than a quintillion quintillion quintillion",
+    possible_passphrases: "Possible passphrases",
+    average_time: "Average time to guess",
+    guesses_per_second: "guesses per second",
+    guesses_per_hour: "guesses per hour",
+    pattern_guesses: "Guesses needed by an attacker who tries patterns first",
+    locked_out: "online, locked out after",
+    tries_per: "tries per",
+    attacks: ["online, throttled", "online, unthrottled", "offline, slow hash", "offline, fast hash"],
+};
+
+const GERMAN: Locale = Locale {
+    decimal: ',',
+    group: '.',
+    magnitudes: ["Tausend", "Millionen", "Milliarden", "Billionen", "Billiarden", "Trillionen"],
+    seconds: "Sekunden",
+    minutes: "Minuten",
+    hours: "Stunden",
+    days: "Tage",
+    years: "Jahre",
+    centuries: "Jahrhunderte",
+    millennia: "Jahrtausende",
+    universe_ages: "{} Mal das Alter des Universums",
+    beyond: "mehr als eine Trillion Trillionen Trillionen",
+    possible_passphrases: "M\u{f6}gliche Passphrasen",
+    average_time: "Durchschnittliche Zeit zum Erraten",
+    guesses_per_second: "Versuche pro Sekunde",
+    guesses_per_hour: "Versuche pro Stunde",
+    pattern_guesses: "N\u{f6}tige Versuche eines Angreifers, der zuerst Muster probiert",
+    locked_out: "online, gesperrt nach",
+    tries_per: "Versuchen pro",
+    attacks: ["online, gebremst", "online, ungebremst", "offline, langsamer Hash", "offline, schneller Hash"],
+};
+
+const SI_PREFIXES: [&str; 10] = ["k", "M", "G", "T", "P", "E", "Z", "Y", "R", "Q"];
+
+const MINUTE: f64 = 60.0;
+const HOUR: f64 = 60.0 * MINUTE;
+const DAY: f64 = 24.0 * HOUR;
+const YEAR: f64 = 365.25 * DAY;
+/// Age of the universe in years
+const UNIVERSE_AGE: f64 = 13.8e9;
+/// How many magnitudes may be stacked, as in "a million quintillion
+/// quintillion", before a number is only said to be huge
+const MAX_STACKED: usize = 3;
+
+impl Locale {
+    /// Picks a locale from LC_ALL, LC_NUMERIC or LANG, falling back to English
+    pub fn from_env() -> &'static Locale {
+        let lang = ["LC_ALL", "LC_NUMERIC", "LANG"].iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+
+        if lang.starts_with("de") { &GERMAN } else { &ENGLISH }
+    }
+
+    fn decimal(&self, value: f64) -> String {
+        format!("{:.1}", value).replace('.', &self.decimal.to_string())
+    }
+
+    fn grouped(&self, value: f64) -> String {
+        let digits = format!("{:.0}", value);
+        let mut out = String::new();
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                out.push(self.group);
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    /// Writes a value below 1000, keeping a decimal place only if it has one
+    fn small(&self, value: f64) -> String {
+        if value.fract() == 0.0 { self.grouped(value) } else { self.decimal(value) }
+    }
+
+    fn scientific(&self, value: f64) -> String {
+        let exponent = value.log10().floor();
+        format!("{} \u{d7} 10^{}", self.decimal(value / 10f64.powf(exponent)), exponent)
+    }
+
+    /// Splits a value into a mantissa and the name of its power of 1000, if
+    /// one of `names` fits
+    fn magnitude(&self, value: f64, names: &[&'static str]) -> (String, &'static str) {
+        if value < 1000.0 {
+            return (self.small(value), "");
+        }
+
+        let power = (value.log10() / 3.0).floor() as usize;
+        if power <= names.len() {
+            (self.decimal(value / 1000f64.powi(power as i32)), names[power - 1])
+        } else {
+            (self.scientific(value), "")
+        }
+    }
+
+    /// Writes a value in named magnitudes, stacking the largest one for
+    /// values beyond it, as in "120 billion quintillion"
+    fn words(&self, value: f64) -> String {
+        let largest = 1000f64.powi(self.magnitudes.len() as i32);
+        let mut stacked = 0;
+        let mut value = value;
+        while value >= largest * 1000.0 {
+            value /= largest;
+            stacked += 1;
+        }
+        if stacked >= MAX_STACKED {
+            return self.beyond.to_string();
+        }
+        let (mantissa, name) = self.magnitude(value, &self.magnitudes);
+        let top = self.magnitudes[self.magnitudes.len() - 1];
+        let mut out = mantissa;
+        for name in std::iter::once(name).chain(std::iter::repeat_n(top, stacked)) {
+            if !name.is_empty() {
+                out.push(' ');
+                out.push_str(name);
+            }
+        }
+        out
+    }
+
+    /// Writes a count such as a number of possible passphrases
+    pub fn number(&self, value: f64, units: Units) -> String {
+        let (mantissa, name) = match units {
+            Units::Exact => return self.grouped(value),
+            Units::Human => return self.words(value),
+            Units::Si => self.magnitude(value, &SI_PREFIXES),
+        };
+        format!("{} {}", mantissa, name).trim_end().to_string()
+    }
+
+    /// Writes a lockout policy such as 5 tries per 15 minutes
+    pub fn lockout(&self, lockout: &Lockout) -> String {
+        format!("{} {} {}", lockout.tries, self.tries_per, humantime::format_duration(lockout.window))
+    }
+
+    /// Writes a duration given in seconds
+    pub fn duration(&self, seconds: f64, units: Units) -> String {
+        match units {
+            Units::Exact if seconds < 1000.0 => format!("{} s", self.small(seconds)),
+            Units::Exact => format!("{} s", self.grouped(seconds)),
+            Units::Si => {
+                let (mantissa, prefix) = self.magnitude(seconds, &SI_PREFIXES);
+                format!("{} {}s", mantissa, prefix)
+            }
+            Units::Human => {
+                let (value, unit) = if seconds < MINUTE {
+                    (seconds, self.seconds)
+                } else if seconds < HOUR {
+                    (seconds / MINUTE, self.minutes)
+                } else if seconds < DAY {
+                    (seconds / HOUR, self.hours)
+                } else if seconds < YEAR {
+                    (seconds / DAY, self.days)
+                } else if seconds < 100.0 * YEAR {
+                    (seconds / YEAR, self.years)
+                } else if seconds < 1000.0 * YEAR {
+                    (seconds / (100.0 * YEAR), self.centuries)
+                } else if seconds < 1_000_000.0 * YEAR {
+                    (seconds / (1000.0 * YEAR), self.millennia)
+                } else if seconds < UNIVERSE_AGE * YEAR {
+                    return format!("{} {}", self.number(seconds / YEAR, Units::Human), self.years);
+                } else {
+                    return self.universe_ages.replace("{}", &self.number(seconds / YEAR / UNIVERSE_AGE, Units::Human));
+                };
+                format!("{} {}", self.decimal(value), unit)
+            }
+        }
+    }
+}
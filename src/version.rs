@@ -0,0 +1,54 @@
+use anyhow::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use passphrs::wordlist::BUNDLED;
+
+#[derive(Serialize)]
+struct Wordlist {
+    name: &'static str,
+    /// Hex SHA-256 of the list contents
+    sha256: String,
+}
+
+/// Everything needed to identify the build that produced a credential
+#[derive(Serialize)]
+struct Provenance {
+    version: &'static str,
+    git_commit: &'static str,
+    target: &'static str,
+    features: Vec<&'static str>,
+    rng: &'static str,
+    wordlists: Vec<Wordlist>,
+}
+
+/// Cargo features this build was compiled with
+const FEATURES: [(&str, bool); 2] = [
+    ("kat", cfg!(feature = "kat")),
+    ("quotes", cfg!(feature = "quotes")),
+];
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+pub fn version_command(verbose: bool) -> Result<()> {
+    if !verbose {
+        println!("passphrs {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+
+    let provenance = Provenance {
+        version: env!("CARGO_PKG_VERSION"),
+        git_commit: env!("PASSPHRS_GIT_COMMIT"),
+        target: env!("PASSPHRS_TARGET"),
+        features: FEATURES.iter().filter(|(_, enabled)| *enabled).map(|(name, _)| *name).collect(),
+        rng: "rand::ThreadRng (ChaCha12 reseeded from the OS via getrandom)",
+        wordlists: BUNDLED.iter()
+            .map(|list| Wordlist { name: list.name(), sha256: sha256_hex(list.text().as_bytes()) })
+            .collect(),
+    };
+
+    println!("{}", serde_json::to_string_pretty(&provenance)?);
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{bail, Result};
+
+use passphrs::constraint::SYMBOLS;
+use passphrs::wordlist::{self, List};
+
+use crate::config::Profile;
+
+/// Fewest words worth generating; below this a random password is stronger
+/// for the same length
+const MIN_WORDS: usize = 4;
+/// Characters of a random password when the site's length limit is too
+/// tight for words
+const RANDOM_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+fn ask(question: &str) -> Result<String> {
+    eprint!("{} ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+        bail!("No answer given, nothing was saved");
+    }
+    Ok(answer.trim().to_string())
+}
+
+fn ask_yes_no(question: &str) -> Result<bool> {
+    loop {
+        match ask(&format!("{} [y/N]", question))?.to_ascii_lowercase().as_str() {
+            "" | "n" | "no" => return Ok(false),
+            "y" | "yes" => return Ok(true),
+            _ => eprintln!("Please answer y or n."),
+        }
+    }
+}
+
+fn ask_limit() -> Result<Option<usize>> {
+    loop {
+        let answer = ask("Does the site limit length? Enter the maximum number of characters, or leave blank:")?;
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        match answer.parse() {
+            Ok(limit) if limit > 0 => return Ok(Some(limit)),
+            _ => eprintln!("Please enter a number of characters."),
+        }
+    }
+}
+
+/// Asks about the site's password rules and how the passphrase will be
+/// entered, and returns settings that satisfy them
+pub fn run() -> Result<Profile> {
+    let limit = ask_limit()?;
+    let symbols = ask_yes_no("Does it require a symbol?")?;
+    let phone = ask_yes_no("Will you type this on a phone?")?;
+
+    // Lowercase words from the short list need no shift key and little
+    // autocorrect fighting on a touch keyboard, and a space is the biggest key
+    let list = if phone { List::EffShort } else { List::EffLarge };
+    // A separator is the one symbol every passphrase is sure to contain
+    let separator = if symbols { "-" } else { " " };
+    let salt = 1;
+
+    let words = if phone { 8 } else { 6 };
+    let longest = wordlist::parse(list.text(), false, None, None).iter()
+        .map(|word| word.chars().count())
+        .max()
+        .unwrap_or(0);
+    let fits = |n: usize| n * longest + (n - 1) * separator.len() + salt;
+    let words = match limit {
+        Some(limit) => (1..=words).take_while(|&n| fits(n) <= limit).last().unwrap_or(0),
+        None => words,
+    };
+
+    if words >= MIN_WORDS {
+        return Ok(Profile {
+            length: Some(words),
+            separator: Some(separator.to_string()),
+            salt_length: Some(salt),
+            case: if phone { Some("lower".to_string()) } else { None },
+            list: crate::arg_name(&list).map(str::to_string),
+            ..Profile::default()
+        });
+    }
+
+    // Too short for enough words to be strong, so fill the limit with
+    // random characters instead
+    let limit = limit.unwrap_or_default();
+    eprintln!("{} characters is too few for {} words, using a random password instead.", limit, MIN_WORDS);
+    let mut profile = Profile {
+        length: Some(0),
+        salt_length: Some(limit),
+        salt_chars: Some(RANDOM_CHARS.to_string()),
+        ..Profile::default()
+    };
+    if symbols {
+        profile.salt_chars = Some(format!("{}{}", RANDOM_CHARS, SYMBOLS));
+        profile.must_end_with = Some("symbol".to_string());
+    }
+    Ok(profile)
+}
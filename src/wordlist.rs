@@ -0,0 +1,77 @@
+use clap::ArgEnum;
+
+/// Word lists compiled into the binary
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq)]
+pub enum List {
+    /// EFF large list: 7776 words, five dice per word
+    EffLarge,
+    /// EFF short list: 1296 shorter words, four dice per word
+    EffShort,
+}
+
+pub const BUNDLED: [List; 2] = [List::EffLarge, List::EffShort];
+
+impl List {
+    pub fn name(self) -> &'static str {
+        match self {
+            List::EffLarge => "eff_large_wordlist.txt",
+            List::EffShort => "eff_short_wordlist_1.txt",
+        }
+    }
+
+    pub fn text(self) -> &'static str {
+        match self {
+            List::EffLarge => include_str!("../eff_large_wordlist.txt"),
+            List::EffShort => include_str!("../eff_short_wordlist_1.txt"),
+        }
+    }
+}
+
+/// Consonants of made-up syllables, leaving out c, q, x and y, which are read
+/// more than one way
+const CONSONANTS: &str = "bdfghjklmnprstvwz";
+const VOWELS: &str = "aeiou";
+
+/// Every consonant-vowel and consonant-vowel-consonant syllable. A run of
+/// them splits back into syllables only one way, since each starts with a
+/// consonant followed by a vowel, so choosing syllables uniformly gives
+/// log2 of their number in bits each.
+pub fn syllables() -> Vec<String> {
+    let mut syllables = Vec::new();
+    for c in CONSONANTS.chars() {
+        for v in VOWELS.chars() {
+            syllables.push(format!("{}{}", c, v));
+            syllables.extend(CONSONANTS.chars().map(|end| format!("{}{}{}", c, v, end)));
+        }
+    }
+    syllables
+}
+
+/// Turns the text of a word list into words, one per line. Unless `raw` is
+/// set, anything but letters (such as dice numbers) is stripped and words
+/// are lowercased. Words outside `min_len..=max_len` characters are dropped.
+pub fn parse(text: &str, raw: bool, min_len: Option<usize>, max_len: Option<usize>) -> Vec<String> {
+    let i_list: Vec<&str> = text.lines().collect();
+    let o_list: Vec<String> = if !raw {
+        i_list.iter()
+            .map(|w| w.chars().filter(|c| c.is_alphabetic()).collect::<String>()
+                 .to_ascii_lowercase())
+            .filter(|w| !w.is_empty())
+            .collect()
+    } else {
+        i_list.iter()
+            .map(|w| w.trim().to_string())
+            .filter(|w| !w.is_empty())
+            .collect()
+    };
+
+    within(o_list, min_len, max_len)
+}
+
+/// Drops the words outside `min_len..=max_len` characters
+pub fn within(words: Vec<String>, min_len: Option<usize>, max_len: Option<usize>) -> Vec<String> {
+    words.into_iter()
+        .filter(|w| min_len.is_none_or(|min| w.chars().count() >= min))
+        .filter(|w| max_len.is_none_or(|max| w.chars().count() <= max))
+        .collect()
+}